@@ -3,6 +3,7 @@ use embedded_graphics::framebuffer::Framebuffer;
 use embedded_graphics::pixelcolor::raw::LittleEndian;
 use embedded_graphics::pixelcolor::Rgb565;
 use embedded_graphics::prelude::ImageDrawable;
+use tinygif::DrawOptions;
 
 fn decode_gif() {
     let im = tinygif::Gif::from_slice(include_bytes!("../assets/Ferris-240x240.gif")).unwrap();
@@ -24,8 +25,58 @@ fn decode_gif() {
     assert_eq!(i, 127);
 }
 
+fn decode_gif_rgb565_lut() {
+    let im =
+        tinygif::Gif::<Rgb565>::from_slice(include_bytes!("../assets/Ferris-240x240.gif")).unwrap();
+
+    let mut fb: Framebuffer<
+        Rgb565,
+        _,
+        LittleEndian,
+        240,
+        240,
+        { embedded_graphics::framebuffer::buffer_size::<Rgb565>(240, 240) },
+    > = Framebuffer::new();
+
+    let mut i = 0;
+    for frame in im.frames() {
+        frame.draw_rgb565(&mut fb).unwrap(); // color mapping with a Rgb565 lookup table
+        i += 1;
+    }
+    assert_eq!(i, 127);
+}
+
+fn decode_gif_opaque() {
+    let im =
+        tinygif::Gif::<Rgb565>::from_slice(include_bytes!("../assets/Ferris-240x240.gif")).unwrap();
+
+    let mut fb: Framebuffer<
+        Rgb565,
+        _,
+        LittleEndian,
+        240,
+        240,
+        { embedded_graphics::framebuffer::buffer_size::<Rgb565>(240, 240) },
+    > = Framebuffer::new();
+
+    // the frames of Ferris are transparent, draw them as opaque ones
+    let options = DrawOptions::new().with_transparency(false);
+    let mut i = 0;
+    for frame in im.frames() {
+        frame.draw_with(&mut fb, &options).unwrap();
+        i += 1;
+    }
+    assert_eq!(i, 127);
+}
+
 fn bench_gif_decoder(c: &mut Criterion) {
     c.bench_function("decode Animated Ferris", |b| b.iter(|| decode_gif()));
+    c.bench_function("decode Animated Ferris with Rgb565 LUT", |b| {
+        b.iter(decode_gif_rgb565_lut)
+    });
+    c.bench_function("decode Animated Ferris as opaque frames", |b| {
+        b.iter(decode_gif_opaque)
+    });
 }
 
 criterion_group!(benches, bench_gif_decoder);
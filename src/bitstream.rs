@@ -1,10 +1,12 @@
 //! Read different number of bits from a byte stream
 
+#[derive(Debug, Clone, Copy)]
 pub struct BitStream<I: Iterator<Item = u8>> {
     r: I,
-    byte: u8,
-    // current bit start pos. LSB fist
-    bit_cursor: u8,
+    // buffered bits, LSB first
+    acc: u32,
+    // number of valid bits in acc
+    nbits: u8,
 }
 
 impl<I> BitStream<I>
@@ -14,35 +16,60 @@ where
     pub fn new(r: I) -> Self {
         Self {
             r,
-            byte: 0,
-            bit_cursor: 8, // point to the LSB of the next byte
+            acc: 0,
+            nbits: 0,
         }
     }
 
-    pub fn next_bits(&mut self, nbit: u8) -> Option<u16> {
-        if nbit >= 16 {
-            panic!("nbit must be < 16");
+    /// Reads `nbit` bits, up to 24.
+    #[inline]
+    pub fn next_bits(&mut self, nbit: u8) -> Option<u32> {
+        debug_assert!(nbit <= 24, "nbit must be <= 24");
+        if self.nbits < nbit {
+            self.fill();
+            if self.nbits < nbit {
+                return None;
+            }
         }
-        if self.bit_cursor == 8 {
-            self.byte = self.r.next()?;
-            self.bit_cursor = 0;
-        }
-        let mut res = (self.byte >> self.bit_cursor) as u16;
-        let mut bits_fullfilled = 8 - self.bit_cursor;
 
-        if bits_fullfilled >= nbit {
-            self.bit_cursor += nbit;
-            return Some(res & ((1u16 << nbit) - 1));
-        }
+        let res = self.acc & ((1u32 << nbit) - 1);
+        self.acc >>= nbit;
+        self.nbits -= nbit;
+        Some(res)
+    }
 
-        while bits_fullfilled < nbit {
-            self.byte = self.r.next()?;
-            res |= (self.byte as u16) << bits_fullfilled;
-            bits_fullfilled += 8;
+    // refill the accumulator with as many whole bytes as fit
+    #[inline]
+    fn fill(&mut self) {
+        while self.nbits <= 24 {
+            match self.r.next() {
+                Some(byte) => {
+                    self.acc |= (byte as u32) << self.nbits;
+                    self.nbits += 8;
+                }
+                None => break,
+            }
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_bits() {
+        let mut bs = BitStream::new([0b1010_1100, 0b0101_0011, 0xff].into_iter());
+        assert_eq!(bs.next_bits(3), Some(0b100));
+        assert_eq!(bs.next_bits(9), Some(0b0011_10101));
+        assert_eq!(bs.next_bits(12), Some(0b1111_1111_0101));
+        assert_eq!(bs.next_bits(1), None);
 
-        self.bit_cursor = nbit - (bits_fullfilled - 8);
-        assert!(self.bit_cursor <= 8);
-        Some(res & ((1u16 << nbit) - 1))
+        let mut bs = BitStream::new([0x34, 0x12, 0x56, 0x78].into_iter());
+        assert_eq!(bs.next_bits(16), Some(0x1234));
+        assert_eq!(bs.next_bits(16), Some(0x7856));
+        let mut bs = BitStream::new([0x34, 0x12, 0x56, 0x78].into_iter());
+        assert_eq!(bs.next_bits(4), Some(0x4));
+        assert_eq!(bs.next_bits(24), Some(0x856123));
     }
 }
@@ -0,0 +1,191 @@
+//! Composing the frames of an animation on a canvas.
+
+use core::convert::Infallible;
+
+use embedded_graphics::draw_target::DrawTargetExt;
+use embedded_graphics::geometry::Dimensions;
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::{
+    DrawTarget, ImageDrawable, OriginDimensions, PixelColor, Point, Size,
+};
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::Pixel;
+
+use crate::{DisposalMethod, DrawError, Frame, ParseError};
+
+// A buffer of pixels in row-major order, pixels outside of it are discarded.
+pub(crate) struct Canvas<'b, C> {
+    pub(crate) pixels: &'b mut [C],
+    pub(crate) size: Size,
+}
+
+impl<C: PixelColor> Canvas<'_, C> {
+    fn fill(&mut self, area: &Rectangle, color: C) {
+        let area = area.intersection(&self.bounding_box());
+        let Some(bottom_right) = area.bottom_right() else {
+            return;
+        };
+        for y in area.top_left.y..=bottom_right.y {
+            let start = (y as u32 * self.size.width) as usize;
+            self.pixels[start + area.top_left.x as usize..=start + bottom_right.x as usize]
+                .fill(color);
+        }
+    }
+}
+
+impl<C> OriginDimensions for Canvas<'_, C> {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl<C: PixelColor> DrawTarget for Canvas<'_, C> {
+    type Color = C;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pt, color) in pixels {
+            if pt.x >= 0
+                && pt.y >= 0
+                && (pt.x as u32) < self.size.width
+                && (pt.y as u32) < self.size.height
+            {
+                self.pixels[(pt.y as u32 * self.size.width + pt.x as u32) as usize] = color;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Draws the frames of an animation on a canvas, disposing each frame before the next
+/// one is drawn.
+///
+/// The canvas is a caller provided buffer holding the logical screen in row-major order.
+/// `DisposalMethod::RestoreToPrevious` would need a second buffer and is not supported,
+/// such frames are left in place.
+pub struct Compositor<'b, C> {
+    canvas: Canvas<'b, C>,
+    background: C,
+    // area and disposal method of the last drawn frame
+    pending_disposal: Option<(Rectangle, DisposalMethod)>,
+}
+
+impl<'b, C: PixelColor> Compositor<'b, C> {
+    /// Creates a compositor for a logical screen of `size`, cleared to `background`.
+    ///
+    /// `ParseError::BufferTooSmall` is returned if `buffer` holds less than
+    /// `size.width * size.height` pixels.
+    pub fn new(buffer: &'b mut [C], size: Size, background: C) -> Result<Self, ParseError> {
+        let len = size.width as usize * size.height as usize;
+        let Some(pixels) = buffer.get_mut(..len) else {
+            return Err(ParseError::BufferTooSmall);
+        };
+        pixels.fill(background);
+        Ok(Self {
+            canvas: Canvas { pixels, size },
+            background,
+            pending_disposal: None,
+        })
+    }
+
+    /// Returns the pixels of the canvas in row-major order.
+    pub fn pixels(&self) -> &[C] {
+        self.canvas.pixels
+    }
+}
+
+impl<C> Compositor<'_, C>
+where
+    C: PixelColor + From<Rgb888>,
+{
+    /// Disposes the previously drawn frame, then draws `frame` on the canvas.
+    ///
+    /// The disposal is applied before any pixel of `frame` is drawn, so the transparent
+    /// pixels of `frame` show the restored background.
+    pub fn draw_frame(&mut self, frame: &Frame<'_, C>) -> Result<(), ParseError> {
+        if let Some((area, DisposalMethod::RestoreToBackground)) = self.pending_disposal.take() {
+            self.canvas.fill(&area, self.background);
+        }
+        self.pending_disposal = Some((frame.bounding_box(), frame.disposal_method));
+        match frame.try_draw(&mut self.canvas) {
+            Ok(()) => Ok(()),
+            Err(DrawError::Parse(e)) => Err(e),
+            Err(DrawError::Draw(e)) => match e {},
+        }
+    }
+}
+
+impl<C> OriginDimensions for Compositor<'_, C> {
+    fn size(&self) -> Size {
+        self.canvas.size
+    }
+}
+
+impl<C: PixelColor> ImageDrawable for Compositor<'_, C> {
+    type Color = C;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        target.fill_contiguous(
+            &Rectangle::new(Point::zero(), self.canvas.size),
+            self.canvas.pixels.iter().copied(),
+        )
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        self.draw(&mut target.translated(-area.top_left).clipped(area))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Gif;
+    use embedded_graphics::pixelcolor::RgbColor;
+
+    // 2x1 screen, frame 0 is red and restores to background, frame 1 has a transparent
+    // pixel followed by a green one
+    const DISPOSE_THEN_TRANSPARENT: &[u8] = b"GIF89a\x02\x00\x01\x00\x81\x00\x00\
+        \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+        \x21\xf9\x04\x08\x0a\x00\x00\x00\
+        \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x4c\x0a\x00\
+        \x21\xf9\x04\x05\x0a\x00\x03\x00\
+        \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x9c\x0a\x00\
+        \x3b";
+
+    #[test]
+    fn test_dispose_to_background_then_transparent() {
+        let gif = Gif::<Rgb888>::from_slice(DISPOSE_THEN_TRANSPARENT).unwrap();
+        let mut buffer = [Rgb888::default(); 2];
+        let mut compositor = Compositor::new(&mut buffer, gif.dimensions(), Rgb888::WHITE).unwrap();
+
+        let mut frames = gif.frames();
+        let frame = frames.next().unwrap();
+        assert_eq!(frame.disposal_method, DisposalMethod::RestoreToBackground);
+        compositor.draw_frame(&frame).unwrap();
+        assert_eq!(compositor.pixels(), &[Rgb888::RED, Rgb888::RED]);
+
+        let frame = frames.next().unwrap();
+        assert_eq!(frame.disposal_method, DisposalMethod::DoNotDispose);
+        compositor.draw_frame(&frame).unwrap();
+        // the transparent pixel reveals the background, not the red of frame 0
+        assert_eq!(compositor.pixels(), &[Rgb888::WHITE, Rgb888::GREEN]);
+    }
+
+    #[test]
+    fn test_buffer_too_small() {
+        let mut buffer = [Rgb888::default(); 3];
+        assert!(matches!(
+            Compositor::new(&mut buffer, Size::new(2, 2), Rgb888::WHITE),
+            Err(ParseError::BufferTooSmall)
+        ));
+    }
+}
@@ -0,0 +1,269 @@
+//! Drawing only the pixels changed since the previous frame.
+
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::{DrawTarget, OriginDimensions, PixelColor, Point, Size};
+use embedded_graphics::primitives::{PointsIter, Rectangle};
+use embedded_graphics::Pixel;
+
+use crate::{
+    lzw, BlockCursor, DisposalMethod, DrawError, ExtensionBlock, Frame, LenPrefixRawDataView,
+    ParseError, Segment,
+};
+
+/// Draws frames on a partial refresh display, skipping pixels unchanged since the
+/// previous frame.
+///
+/// The color indices of the logical screen are kept in a caller provided buffer, to be
+/// compared with the indices of the next frame. Areas of frames disposed with
+/// `DisposalMethod::RestoreToBackground` are restored to the background color index of
+/// the GIF. Indices are only compared while all frames use the global color table, once
+/// a frame with a local color table is drawn, all pixels of the following frames are drawn.
+pub struct DiffDrawer<'b> {
+    indices: &'b mut [u8],
+    size: Size,
+    bg_color_index: u8,
+    // area of the last drawn frame, restored to the background before the next frame
+    pending_disposal: Option<Rectangle>,
+    has_local_color_table: bool,
+}
+
+impl<'b> DiffDrawer<'b> {
+    /// Buffers the color indices of `previous`, which must already be drawn on the target.
+    ///
+    /// The logical screen is assumed to be cleared to the background before `previous`
+    /// was drawn. `ParseError::BufferTooSmall` is returned if `buffer` can't hold the
+    /// logical screen.
+    pub fn new<C>(buffer: &'b mut [u8], previous: &Frame<'_, C>) -> Result<Self, ParseError> {
+        let size = previous.size();
+        let len = (size.width * size.height) as usize;
+        let Some(indices) = buffer.get_mut(..len) else {
+            return Err(ParseError::BufferTooSmall);
+        };
+        indices.fill(previous.header.bg_color_index);
+        previous.decode_indices(indices)?;
+
+        let mut drawer = Self {
+            indices,
+            size,
+            bg_color_index: previous.header.bg_color_index,
+            pending_disposal: None,
+            has_local_color_table: false,
+        };
+        drawer.update_state(previous);
+        Ok(drawer)
+    }
+
+    /// Draws the pixels of `frame` differing from the previous frame.
+    pub fn draw<C, D>(
+        &mut self,
+        frame: &Frame<'_, C>,
+        target: &mut D,
+    ) -> Result<(), DrawError<D::Error>>
+    where
+        C: PixelColor + From<Rgb888>,
+        D: DrawTarget<Color = C>,
+    {
+        let disposal = self.pending_disposal.take();
+        self.update_state(frame);
+        let compare = !self.has_local_color_table;
+
+        let screen_width = self.size.width as usize;
+        let screen_height = self.size.height as usize;
+        let bg_color_index = self.bg_color_index;
+        let background = frame
+            .global_color_table
+            .and_then(|table| table.get(bg_color_index))
+            .map(C::from);
+        let indices = &mut *self.indices;
+
+        // restore the part of the disposed area that is not covered by this frame,
+        // the covered part is restored where this frame is transparent
+        let frame_area = frame.bounding_box();
+        if let Some(area) = disposal {
+            let area = area.intersection(&Rectangle::new(Point::zero(), self.size));
+            target
+                .draw_iter(
+                    area.points()
+                        .filter(|pt| !frame_area.contains(*pt))
+                        .filter_map(|pt| {
+                            // without background color, the pixel and its index are left
+                            let background = background?;
+                            let i = pt.y as usize * screen_width + pt.x as usize;
+                            if compare && indices[i] == bg_color_index {
+                                return None;
+                            }
+                            indices[i] = bg_color_index;
+                            Some(Pixel(pt, background))
+                        }),
+                )
+                .map_err(DrawError::Draw)?;
+        }
+
+        let transparent_color_index = if frame.is_transparent {
+            Some(frame.transparent_color_index)
+        } else {
+            None
+        };
+        let mut input = frame.raw_data;
+        loop {
+            let (input0, seg) = Segment::parse(input).map_err(DrawError::Parse)?;
+            input = input0;
+            match seg {
+                Segment::Extension(ExtensionBlock::GraphicControl(_)) | Segment::Trailer => break,
+                Segment::Image(image_block) if image_block.width > 0 => {
+                    let Some(color_table) =
+                        image_block.local_color_table.or(frame.global_color_table)
+                    else {
+                        return Err(DrawError::Parse(ParseError::MissingColorTable));
+                    };
+                    let raw_image_data = LenPrefixRawDataView::new(image_block.image_data);
+                    let mut decoder =
+                        lzw::Decoder::new(raw_image_data, image_block.lzw_min_code_size);
+
+                    let mut cursor = BlockCursor::new(&image_block);
+                    while let Some(decoded) = decoder.decode_next().map_err(DrawError::Parse)? {
+                        // codes past the last pixel are ignored
+                        let decoded = &decoded[..decoded.len().min(cursor.remaining())];
+                        target
+                            .draw_iter(decoded.iter().filter_map(|&color_index| {
+                                let (x, y) = cursor.advance();
+                                let (x, y) = (x as usize, y as usize);
+                                if x >= screen_width || y >= screen_height {
+                                    return None;
+                                }
+                                let pt = Point::new(x as i32, y as i32);
+
+                                let (color_index, color) =
+                                    if transparent_color_index == Some(color_index) {
+                                        if !disposal.is_some_and(|area| area.contains(pt)) {
+                                            // the previous content is left in place
+                                            return None;
+                                        }
+                                        (bg_color_index, background)
+                                    } else {
+                                        (color_index, color_table.get(color_index).map(C::from))
+                                    };
+                                // indices are only recorded for drawn pixels
+                                let color = color?;
+                                let i = y * screen_width + x;
+                                if compare && indices[i] == color_index {
+                                    return None;
+                                }
+                                indices[i] = color_index;
+                                Some(Pixel(pt, color))
+                            }))
+                            .map_err(DrawError::Draw)?;
+                        if cursor.remaining() == 0 {
+                            break;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    // Records the disposal and the color table of the frame being drawn.
+    fn update_state<C>(&mut self, frame: &Frame<'_, C>) {
+        if frame.disposal_method == DisposalMethod::RestoreToBackground {
+            self.pending_disposal = Some(frame.bounding_box());
+        }
+        self.has_local_color_table |= frame
+            .image_block()
+            .is_ok_and(|image_block| image_block.local_color_table.is_some());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Gif;
+    use embedded_graphics::pixelcolor::RgbColor;
+
+    // Records drawn pixels
+    struct Recorder(heapless::Vec<Pixel<Rgb888>, 8>);
+
+    impl OriginDimensions for Recorder {
+        fn size(&self) -> Size {
+            Size::new(2, 1)
+        }
+    }
+
+    impl DrawTarget for Recorder {
+        type Color = Rgb888;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for pixel in pixels {
+                self.0.push(pixel).unwrap();
+            }
+            Ok(())
+        }
+    }
+
+    // 2x1 screen, frame 0 is red and restores to background, frame 1 is transparent
+    // then red, frame 2 is red then green
+    const THREE_FRAMES: &[u8] = b"GIF89a\x02\x00\x01\x00\x81\x00\x00\
+        \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+        \x21\xf9\x04\x08\x0a\x00\x00\x00\
+        \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x4c\x0a\x00\
+        \x21\xf9\x04\x01\x0a\x00\x03\x00\
+        \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x5c\x0a\x00\
+        \x21\xf9\x04\x00\x0a\x00\x00\x00\
+        \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x8c\x0a\x00\
+        \x3b";
+
+    #[test]
+    fn test_draw_changed_pixels() {
+        let gif = Gif::<Rgb888>::from_slice(THREE_FRAMES).unwrap();
+        let mut frames = gif.frames();
+        let mut buffer = [0; 2];
+        let mut drawer = DiffDrawer::new(&mut buffer, &frames.next().unwrap()).unwrap();
+
+        // the disposed pixel shows through the transparent one, the other is unchanged
+        let mut recorder = Recorder(heapless::Vec::new());
+        drawer.draw(&frames.next().unwrap(), &mut recorder).unwrap();
+        assert_eq!(recorder.0, [Pixel(Point::new(0, 0), Rgb888::BLACK)]);
+
+        let mut recorder = Recorder(heapless::Vec::new());
+        drawer.draw(&frames.next().unwrap(), &mut recorder).unwrap();
+        assert_eq!(
+            recorder.0,
+            [
+                Pixel(Point::new(0, 0), Rgb888::RED),
+                Pixel(Point::new(1, 0), Rgb888::GREEN)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undrawn_pixels_keep_indices() {
+        // 2x1 screen with 2 colors, frame 0 is red, frame 1 has an index out of the color
+        // table then red, frame 2 is red again
+        let data = b"GIF89a\x02\x00\x01\x00\x80\x00\x00\x00\x00\x00\xff\x00\x00\
+            \x21\xf9\x04\x00\x0a\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x4c\x0a\x00\
+            \x21\xf9\x04\x00\x0a\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x5c\x0a\x00\
+            \x21\xf9\x04\x00\x0a\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x4c\x0a\x00\
+            \x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let mut frames = gif.frames();
+        let mut buffer = [0; 2];
+        let mut drawer = DiffDrawer::new(&mut buffer, &frames.next().unwrap()).unwrap();
+
+        // nothing is drawn, the red pixel stays on the display
+        let mut recorder = Recorder(heapless::Vec::new());
+        drawer.draw(&frames.next().unwrap(), &mut recorder).unwrap();
+        assert_eq!(recorder.0, []);
+
+        let mut recorder = Recorder(heapless::Vec::new());
+        drawer.draw(&frames.next().unwrap(), &mut recorder).unwrap();
+        assert_eq!(recorder.0, []);
+    }
+}
@@ -0,0 +1,283 @@
+//! Frame by frame decoding from an `embedded_io::Read` source.
+
+use core::marker::PhantomData;
+
+use embedded_io::{Read, ReadExactError};
+
+use crate::{
+    color_table_size, ColorTable, ExtensionBlock, Frame, GraphicControl, Header, ParseError,
+};
+
+// Logical Screen Descriptor, plus the largest global color table
+const MAX_HEADER_LEN: usize = 13 + 256 * 3;
+
+impl<E> From<ReadExactError<E>> for ParseError {
+    fn from(err: ReadExactError<E>) -> Self {
+        match err {
+            ReadExactError::UnexpectedEof => ParseError::UnexpectedEndOfFile,
+            ReadExactError::Other(_) => ParseError::Io,
+        }
+    }
+}
+
+/// A GIF read frame by frame from an `embedded_io::Read` source.
+///
+/// Only the header and the current frame are kept in memory. `N` is the capacity of the
+/// frame buffer in bytes, it must be large enough for the compressed data of each frame.
+///
+/// This is a separate type rather than a `Gif::from_io` constructor, as `Gif` borrows the
+/// whole file while the frames read here borrow the frame buffer owned by the reader.
+pub struct StreamingGif<R, C, const N: usize> {
+    reader: R,
+    header: Header,
+    header_data: [u8; MAX_HEADER_LEN],
+    header_len: usize,
+    global_color_table_sorted: bool,
+    buffer: heapless::Vec<u8, N>,
+    // Graphic Control read at the end of the previous frame
+    pending_ctrl: Option<GraphicControl>,
+    frame_index: usize,
+    done: bool,
+    color_type: PhantomData<C>,
+}
+
+impl<R: Read, C, const N: usize> StreamingGif<R, C, N> {
+    /// Reads the header and the global color table.
+    pub fn from_io(mut reader: R) -> Result<Self, ParseError> {
+        let mut header_data = [0; MAX_HEADER_LEN];
+        reader.read_exact(&mut header_data[..13])?;
+        let flags = header_data[10];
+        let header_len = if flags & 0b1000_0000 != 0 {
+            13 + 3 * color_table_size(flags)
+        } else {
+            13
+        };
+        reader.read_exact(&mut header_data[13..header_len])?;
+        let (_, (header, global_color_table)) = Header::parse(&header_data[..header_len])?;
+        let global_color_table_sorted = global_color_table.is_some_and(|table| table.is_sorted());
+
+        Ok(Self {
+            reader,
+            header,
+            header_data,
+            header_len,
+            global_color_table_sorted,
+            buffer: heapless::Vec::new(),
+            pending_ctrl: None,
+            frame_index: 0,
+            done: false,
+            color_type: PhantomData,
+        })
+    }
+
+    pub fn width(&self) -> u16 {
+        self.header.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.header.height
+    }
+
+    /// Reads the next frame, `None` is returned after the trailer.
+    ///
+    /// As `Gif::frames`, a frame holds all the image blocks up to the next Graphic Control
+    /// Extension. The returned frame borrows the frame buffer, which is reused by the next
+    /// call.
+    pub fn next_frame(&mut self) -> Result<Option<Frame<'_, C>>, ParseError> {
+        if self.done {
+            return Ok(None);
+        }
+        self.buffer.clear();
+
+        // the frame starts at its Graphic Control, or at its first image block without one
+        let mut ctrl = self.pending_ctrl.take();
+        let mut has_image = false;
+        loop {
+            match self.read_byte()? {
+                0x21 => {
+                    let label = self.read_byte()?;
+                    if label == 0xf9 {
+                        let next_ctrl = self.read_graphic_control()?;
+                        if ctrl.is_some() || has_image {
+                            // first segment of the next frame
+                            self.pending_ctrl = Some(next_ctrl);
+                            break;
+                        }
+                        ctrl = Some(next_ctrl);
+                    } else if ctrl.is_some() || has_image {
+                        self.push_to_buffer(0x21)?;
+                        self.push_to_buffer(label)?;
+                        self.buffer_subblocks()?;
+                    } else {
+                        self.skip_subblocks()?;
+                    }
+                }
+                0x2c => {
+                    self.buffer_image_block()?;
+                    has_image = true;
+                }
+                0x3b => {
+                    self.done = true;
+                    if ctrl.is_none() && !has_image {
+                        return Ok(None);
+                    }
+                    break;
+                }
+                _ => return Err(ParseError::InvalidByte),
+            }
+        }
+        // ends the buffer, so that it can be parsed as the raw data of a frame
+        self.push_to_buffer(0x3b)?;
+        let ctrl = ctrl.unwrap_or_default();
+
+        // the Logical Screen Descriptor is 13 bytes long
+        let global_color_table = self.header.has_global_color_table.then(|| {
+            ColorTable::new(&self.header_data[13..self.header_len])
+                .with_sorted(self.global_color_table_sorted)
+        });
+        let frame = Frame {
+            delay_centis: ctrl.delay_centis,
+            is_transparent: ctrl.is_transparent,
+            transparent_color_index: ctrl.transparent_color_index,
+            disposal_method: ctrl.disposal_method,
+            user_input: ctrl.user_input,
+            global_color_table,
+            header: &self.header,
+            raw_data: &self.buffer,
+            frame_index: self.frame_index,
+            _marker: PhantomData,
+        };
+        self.frame_index += 1;
+        Ok(Some(frame))
+    }
+
+    fn read_byte(&mut self) -> Result<u8, ParseError> {
+        let mut byte = [0];
+        self.reader.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    // Read `len` bytes to the end of the frame buffer.
+    fn read_to_buffer(&mut self, len: usize) -> Result<(), ParseError> {
+        let start = self.buffer.len();
+        self.buffer
+            .resize(start + len, 0)
+            .map_err(|_| ParseError::BufferTooSmall)?;
+        self.reader.read_exact(&mut self.buffer[start..])?;
+        Ok(())
+    }
+
+    fn push_to_buffer(&mut self, byte: u8) -> Result<(), ParseError> {
+        self.buffer
+            .push(byte)
+            .map_err(|_| ParseError::BufferTooSmall)
+    }
+
+    // Read a Graphic Control Extension after its label.
+    fn read_graphic_control(&mut self) -> Result<GraphicControl, ParseError> {
+        let mut ext = [0xf9; 7];
+        self.reader.read_exact(&mut ext[1..])?;
+        match ExtensionBlock::parse(&ext)? {
+            (_, ExtensionBlock::GraphicControl(ctrl)) => Ok(ctrl),
+            _ => Err(ParseError::InvalidByte),
+        }
+    }
+
+    // Read length prefixed sub-blocks to the end of the frame buffer, terminator included.
+    fn buffer_subblocks(&mut self) -> Result<(), ParseError> {
+        loop {
+            let len = self.read_byte()?;
+            self.push_to_buffer(len)?;
+            if len == 0 {
+                return Ok(());
+            }
+            self.read_to_buffer(len as usize)?;
+        }
+    }
+
+    fn skip_subblocks(&mut self) -> Result<(), ParseError> {
+        let mut block = [0; 255];
+        loop {
+            let len = self.read_byte()? as usize;
+            if len == 0 {
+                return Ok(());
+            }
+            self.reader.read_exact(&mut block[..len])?;
+        }
+    }
+
+    // Buffer an image block after the 0x2c separator.
+    fn buffer_image_block(&mut self) -> Result<(), ParseError> {
+        let start = self.buffer.len();
+        self.push_to_buffer(0x2c)?;
+        self.read_to_buffer(9)?;
+        let flags = self.buffer[start + 9];
+        if flags & 0b1000_0000 != 0 {
+            self.read_to_buffer(3 * color_table_size(flags))?;
+        }
+        // LZW minimum code size
+        self.read_to_buffer(1)?;
+        self.buffer_subblocks()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Gif;
+    use embedded_graphics::pixelcolor::Rgb888;
+
+    fn assert_same_frames(data: &[u8]) {
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let mut stream = StreamingGif::<_, Rgb888, 8192>::from_io(data).unwrap();
+        assert_eq!(stream.width(), gif.width());
+        assert_eq!(stream.height(), gif.height());
+
+        let mut frames = gif.frames();
+        while let Some(streamed) = stream.next_frame().unwrap() {
+            let frame = frames.next().unwrap();
+            assert_eq!(streamed.delay_centis, frame.delay_centis);
+            assert_eq!(streamed.is_transparent, frame.is_transparent);
+            assert_eq!(
+                streamed.transparent_color_index,
+                frame.transparent_color_index
+            );
+            assert_eq!(streamed.disposal_method, frame.disposal_method);
+            assert_eq!(streamed.user_input, frame.user_input);
+            assert!(streamed.pixels().eq(frame.pixels()));
+        }
+        assert!(frames.next().is_none());
+        assert!(stream.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_streaming_frames() {
+        assert_same_frames(include_bytes!("../assets/Ferris-160x80.gif"));
+        assert_same_frames(include_bytes!("../assets/Ferris-128x64.gif"));
+        // 4x3 logical screen, frame 0 has a comment and one pixel image blocks at the top
+        // left and bottom right, frame 1 is transparent with one image block
+        let data = b"GIF89a\x04\x00\x03\x00\x80\x00\x00\x00\x00\x00\xff\x00\x00\
+            \x21\xf9\x04\x04\x05\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x4c\x01\x00\
+            \x21\xfe\x02hi\x00\
+            \x2c\x03\x00\x02\x00\x01\x00\x01\x00\x00\x02\x02\x4c\x01\x00\
+            \x21\xf9\x04\x05\x07\x00\x00\x00\
+            \x2c\x01\x00\x01\x00\x01\x00\x01\x00\x00\x02\x02\x4c\x01\x00\
+            \x3b";
+        assert_same_frames(data);
+        let mut stream = StreamingGif::<_, Rgb888, 64>::from_io(&data[..]).unwrap();
+        let frame = stream.next_frame().unwrap().unwrap();
+        assert_eq!(frame.delay_centis, 5);
+        assert_eq!(frame.pixels().count(), 2);
+        let frame = stream.next_frame().unwrap().unwrap();
+        assert_eq!(frame.delay_centis, 7);
+        assert!(stream.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_streaming_buffer_too_small() {
+        let data: &[u8] = include_bytes!("../assets/Ferris-160x80.gif");
+        let mut stream = StreamingGif::<_, Rgb888, 16>::from_io(data).unwrap();
+        assert_eq!(stream.next_frame().err(), Some(ParseError::BufferTooSmall));
+    }
+}
@@ -2,21 +2,112 @@
 
 #![no_std]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::cell::Cell;
 use core::fmt::{self, Debug};
 use core::marker::PhantomData;
+use core::time::Duration;
 
+use embedded_graphics::draw_target::DrawTargetExt;
+#[cfg(feature = "framebuffer")]
+use embedded_graphics::framebuffer::Framebuffer;
 use embedded_graphics::prelude::{DrawTarget, ImageDrawable, OriginDimensions, Point, Size};
+use embedded_graphics::primitives::{PointsIter, Rectangle};
 use embedded_graphics::Pixel;
-use embedded_graphics::{pixelcolor::Rgb888, prelude::PixelColor};
+use embedded_graphics::{
+    pixelcolor::{BinaryColor, Rgb565, Rgb888, RgbColor},
+    prelude::PixelColor,
+};
 use parser::eat_len_prefixed_subblocks;
 
 use crate::parser::{le_u16, take, take1, take_slice};
 
 mod bitstream;
+mod compositor;
+mod diff;
+#[cfg(feature = "embedded-io")]
+mod io;
 pub mod lzw;
+mod options;
 mod parser;
+mod tiled;
+
+use compositor::Canvas;
+pub use compositor::Compositor;
+pub use diff::DiffDrawer;
+#[cfg(feature = "embedded-io")]
+pub use io::StreamingGif;
+pub use options::{DrawOptions, Rotation};
+use options::{Rotated, RowTracker, Scaled, Stretched};
+pub use tiled::TiledFrame;
+
+/// Returns true if `input` starts with the `GIF87a` or `GIF89a` signature.
+///
+/// Only the first 6 bytes are checked, for format sniffing.
+pub fn is_gif(input: &[u8]) -> bool {
+    matches!(input.get(..6), Some(b"GIF87a" | b"GIF89a"))
+}
+
+/// Returns the logical screen `(width, height)` of a GIF, usable in const contexts.
+///
+/// Meant for GIFs embedded with `include_bytes!`, to size buffers at compile time:
+/// `const SIZE: (u16, u16) = tinygif::parse_dimensions(include_bytes!("image.gif"));`.
+/// Only the signature is checked, panics if `bytes` is not a GIF, which fails the build
+/// when evaluated at compile time. Use `probe` for untrusted input.
+pub const fn parse_dimensions(bytes: &[u8]) -> (u16, u16) {
+    assert!(
+        bytes.len() >= 10
+            && bytes[0] == b'G'
+            && bytes[1] == b'I'
+            && bytes[2] == b'F'
+            && bytes[3] == b'8'
+            && (bytes[4] == b'7' || bytes[4] == b'9')
+            && bytes[5] == b'a',
+        "not a GIF"
+    );
+    (
+        u16::from_le_bytes([bytes[6], bytes[7]]),
+        u16::from_le_bytes([bytes[8], bytes[9]]),
+    )
+}
+
+/// Parses only the header and the global color table, without constructing a full `Gif`.
+pub fn probe(input: &[u8]) -> Result<Header, ParseError> {
+    Header::parse(input).map(|(_, (header, _))| header)
+}
+
+/// Splits GIFs stored back to back, such as packed sprite sheets.
+///
+/// Each GIF ends with its trailer, the next one starts right after it. The iterator stops
+/// after the first GIF that fails to parse.
+pub fn split<C>(bytes: &[u8]) -> impl Iterator<Item = Result<Gif<'_, C>, ParseError>> {
+    let mut input = bytes;
+    core::iter::from_fn(move || {
+        if input.is_empty() {
+            return None;
+        }
+        let result = RawGif::from_slice(input).and_then(|mut raw_gif| {
+            let (blocks, remain) = Segment::split_after_trailer(raw_gif.raw_block_data)?;
+            raw_gif.raw_block_data = blocks;
+            input = remain;
+            Ok(Gif {
+                raw_gif,
+                color_type: PhantomData,
+            })
+        });
+        if result.is_err() {
+            input = &[];
+        }
+        Some(result)
+    })
+}
 
 /// Len byte prefixed raw bytes, as used in GIFs.
+#[derive(Debug, Clone, Copy)]
 struct LenPrefixRawDataView<'a> {
     remains: &'a [u8],
     current_block: &'a [u8],
@@ -24,12 +115,32 @@ struct LenPrefixRawDataView<'a> {
 }
 
 impl<'a> LenPrefixRawDataView<'a> {
+    /// Creates a view of `data`, which starts with a len byte.
+    ///
+    /// Empty data or a block terminator give an empty view, a truncated sub-block is
+    /// read up to the end of `data`.
     pub fn new(data: &'a [u8]) -> Self {
-        let len = data[0] as usize;
-        Self {
-            remains: &data[1 + len..],
-            current_block: &data[1..1 + len],
+        let mut view = Self {
+            remains: data,
+            current_block: &[],
             cursor: 0,
+        };
+        view.read_block();
+        view
+    }
+
+    // read the next sub-block from `remains`
+    fn read_block(&mut self) {
+        match self.remains.split_first() {
+            Some((&len, remains)) if len > 0 => {
+                let len = (len as usize).min(remains.len());
+                self.current_block = &remains[..len];
+                self.remains = &remains[len..];
+            }
+            _ => {
+                self.remains = &[];
+                self.current_block = &[];
+            }
         }
     }
 
@@ -52,14 +163,7 @@ impl<'a> LenPrefixRawDataView<'a> {
             // no more blocks
             return;
         }
-        let len = self.remains[0] as usize;
-        if len == 0 {
-            self.remains = &[];
-            self.current_block = &[];
-        } else {
-            self.current_block = &self.remains[1..1 + len];
-            self.remains = &self.remains[1 + len..];
-        }
+        self.read_block();
     }
 }
 
@@ -78,6 +182,29 @@ impl Iterator for LenPrefixRawDataView<'_> {
     }
 }
 
+/// Maps the n-th decoded row of an interlaced image to its row in the image.
+///
+/// Interlaced rows are stored in 4 passes: every 8th row from row 0, every 8th row
+/// from row 4, every 4th row from row 2, and every 2nd row from row 1.
+fn interlaced_row(n: usize, height: usize) -> usize {
+    let mut n = n;
+    for (start, step) in [(0, 8), (4, 8), (2, 4), (1, 2)] {
+        let rows = (height + step - 1).saturating_sub(start) / step;
+        if n < rows {
+            return start + n * step;
+        }
+        n -= rows;
+    }
+    height + n
+}
+
+/// Number of entries of a color table, from the 3 size bits of the packed fields.
+fn color_table_size(flags: u8) -> usize {
+    // at most 2^8 entries, as only 3 bits are used
+    1 << ((flags & 0b0000_0111) + 1)
+}
+
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[non_exhaustive]
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum Version {
@@ -85,6 +212,10 @@ pub enum Version {
     V89a,
 }
 
+/// Result of `Header::parse`: the remaining input, the header and the global color table.
+pub type HeaderParse<'a> = (&'a [u8], (Header, Option<ColorTable<'a>>));
+
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct Header {
     version: Version,
@@ -92,13 +223,51 @@ pub struct Header {
     pub height: u16,
     has_global_color_table: bool,
     color_resolution: u8, // 3 bits
-    // _is_sorted: bool,
+    global_color_count: u16,
     pub bg_color_index: u8,
-    // _pixel_aspect_ratio: u8
+    pixel_aspect_ratio: u8,
 }
 
 impl Header {
-    pub fn parse(input: &[u8]) -> Result<(&[u8], (Header, Option<ColorTable<'_>>)), ParseError> {
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn has_global_color_table(&self) -> bool {
+        self.has_global_color_table
+    }
+
+    /// Bits per primary color minus 1, as stored in the Logical Screen Descriptor.
+    pub fn color_resolution(&self) -> u8 {
+        self.color_resolution
+    }
+
+    /// Number of colors in the global color table, 0 if there is none.
+    pub fn global_color_count(&self) -> usize {
+        self.global_color_count as usize
+    }
+
+    /// Pixel aspect ratio, as stored in the Logical Screen Descriptor.
+    ///
+    /// The width of a pixel divided by its height is `(ratio + 15) / 64`, `0` means no
+    /// aspect ratio information, pixels are square.
+    pub fn pixel_aspect_ratio(&self) -> u8 {
+        self.pixel_aspect_ratio
+    }
+
+    pub fn parse(input: &[u8]) -> Result<HeaderParse<'_>, ParseError> {
+        Self::parse_with(input, false)
+    }
+
+    /// Parses a header, accepting a truncated global color table.
+    ///
+    /// The entries missing from the input are black, and the returned remaining input is
+    /// empty.
+    pub fn parse_lenient(input: &[u8]) -> Result<HeaderParse<'_>, ParseError> {
+        Self::parse_with(input, true)
+    }
+
+    fn parse_with(input: &[u8], lenient: bool) -> Result<HeaderParse<'_>, ParseError> {
         let (input, magic) = take::<3>(input)?;
 
         if &magic != b"GIF" {
@@ -116,24 +285,37 @@ impl Header {
 
         let (intput, screen_width) = le_u16(input)?;
         let (intput, screen_height) = le_u16(intput)?;
+        if screen_width == 0 || screen_height == 0 {
+            return Err(ParseError::InvalidImageDimensions);
+        }
 
         let (input, flags) = take1(intput)?;
         let has_global_color_table = flags & 0b1000_0000 != 0;
         let global_color_table_size = if has_global_color_table {
-            2_usize.pow(((flags & 0b0000_0111) + 1) as u32)
+            color_table_size(flags)
         } else {
             0
         };
         let color_resolution = (flags & 0b0111_0000) >> 4;
-        let _is_sorted = flags & 0b0000_1000 != 0;
+        let is_sorted = flags & 0b0000_1000 != 0;
 
         let (input, bg_color_index) = take1(input)?;
-        let (input, _pixel_aspect_ratio) = take1(input)?;
+        let (input, pixel_aspect_ratio) = take1(input)?;
+        if !lenient && input.len() < global_color_table_size * 3 {
+            return Err(ParseError::TruncatedColorTable);
+        }
 
         let (input, color_table) = if global_color_table_size > 0 {
             // Each color table entry is 3 bytes long
-            let (input, table) = take_slice(input, global_color_table_size * 3)?;
-            (input, Some(ColorTable::new(table)))
+            let (input, table) = if lenient && input.len() < global_color_table_size * 3 {
+                (&input[input.len()..], &input[..input.len() / 3 * 3])
+            } else {
+                take_slice(input, global_color_table_size * 3)?
+            };
+            let table = ColorTable::new(table)
+                .with_sorted(is_sorted)
+                .with_padding(global_color_table_size);
+            (input, Some(table))
         } else {
             (input, None)
         };
@@ -147,7 +329,9 @@ impl Header {
                     height: screen_height,
                     has_global_color_table,
                     color_resolution,
+                    global_color_count: global_color_table_size as u16,
                     bg_color_index,
+                    pixel_aspect_ratio,
                 },
                 color_table,
             ),
@@ -158,16 +342,55 @@ impl Header {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ColorTable<'a> {
     data: &'a [u8],
+    // number of entries, entries missing from data are black
+    len: usize,
+    is_sorted: bool,
 }
 
 impl<'a> ColorTable<'a> {
     pub(crate) const fn new(data: &'a [u8]) -> Self {
-        Self { data }
+        Self {
+            data,
+            len: data.len() / 3,
+            is_sorted: false,
+        }
+    }
+
+    /// Creates a color table from R, G, B bytes, such as a palette of the caller.
+    ///
+    /// `ParseError::InvalidColorTableLength` is returned if `data` is not a whole number of
+    /// entries, or holds more than 256 entries.
+    // `is_multiple_of` needs Rust 1.87
+    #[allow(clippy::manual_is_multiple_of)]
+    pub const fn from_rgb888_slice(data: &'a [u8]) -> Result<Self, ParseError> {
+        if data.len() % 3 != 0 || data.len() > 256 * 3 {
+            return Err(ParseError::InvalidColorTableLength);
+        }
+        Ok(Self::new(data))
+    }
+
+    pub(crate) const fn with_sorted(self, is_sorted: bool) -> Self {
+        Self { is_sorted, ..self }
+    }
+
+    // Pads the table with black entries up to `len` entries.
+    const fn with_padding(self, len: usize) -> Self {
+        if len > self.len {
+            Self { len, ..self }
+        } else {
+            self
+        }
+    }
+
+    /// Returns `true` if the entries are sorted by decreasing importance,
+    /// most frequently used colors first.
+    pub const fn is_sorted(&self) -> bool {
+        self.is_sorted
     }
 
     /// Returns the number of entries.
     pub const fn len(&self) -> usize {
-        self.data.len() / 3
+        self.len
     }
 
     /// Returns a color table entry.
@@ -176,7 +399,7 @@ impl<'a> ColorTable<'a> {
     pub fn get(&self, index: u8) -> Option<Rgb888> {
         let base = 3 * (index as usize);
         if base >= self.data.len() {
-            return None;
+            return (usize::from(index) < self.len).then_some(Rgb888::BLACK);
         }
 
         Some(Rgb888::new(
@@ -185,6 +408,63 @@ impl<'a> ColorTable<'a> {
             self.data[base + 2],
         ))
     }
+
+    /// Returns a color table entry, without bounds checking.
+    ///
+    /// For hot loops over indices already checked against the table, `get` checks each
+    /// index again.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be less than the number of entries stored in the table, which is
+    /// `len()` unless the table was padded by `Header::parse_lenient`.
+    pub unsafe fn get_unchecked(&self, index: u8) -> Rgb888 {
+        let base = 3 * (index as usize);
+        debug_assert!(base + 3 <= self.data.len(), "index out of bounds");
+        // SAFETY: the entry is in bounds, as required by the caller
+        let entry = unsafe { self.data.get_unchecked(base..base + 3) };
+        Rgb888::new(entry[0], entry[1], entry[2])
+    }
+
+    /// Returns all entries converted to `Rgb565`.
+    ///
+    /// Indexing the returned table is faster than converting each drawn pixel.
+    pub fn to_rgb565_lut(&self) -> heapless::Vec<Rgb565, 256> {
+        self.data
+            .chunks_exact(3)
+            .map(|entry| Rgb888::new(entry[0], entry[1], entry[2]).into())
+            .chain(core::iter::repeat(Rgb565::BLACK))
+            .take(self.len.min(256))
+            .collect()
+    }
+
+    /// Returns the index of the entry closest to `color`, by euclidean distance in RGB space.
+    ///
+    /// `0` is returned for an empty table.
+    pub fn nearest(&self, color: Rgb888) -> u8 {
+        let mut nearest = 0;
+        let mut min_distance = u32::MAX;
+        for (index, entry) in self.data.chunks_exact(3).enumerate().take(256) {
+            let distance = [color.r(), color.g(), color.b()]
+                .iter()
+                .zip(entry)
+                .map(|(&a, &b)| (a.abs_diff(b) as u32).pow(2))
+                .sum();
+            if distance < min_distance {
+                min_distance = distance;
+                nearest = index as u8;
+            }
+        }
+        nearest
+    }
+
+    /// Returns `true` if both tables have the same entries.
+    ///
+    /// Unlike `==`, the sort flag is ignored. Frames sharing the same table data are
+    /// compared without reading the entries.
+    pub fn colors_eq(&self, other: &ColorTable<'_>) -> bool {
+        self.len == other.len && (core::ptr::eq(self.data, other.data) || self.data == other.data)
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -208,18 +488,79 @@ impl<'a> RawGif<'a> {
             raw_block_data: remaining,
         })
     }
+
+    fn from_slice_lenient(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let mut raw_gif = Self::from_slice(bytes)?;
+        // the blocks are kept unchanged if no trailer is found
+        if let Ok((blocks, _)) = Segment::split_after_trailer(raw_gif.raw_block_data) {
+            raw_gif.raw_block_data = blocks;
+        }
+        Ok(raw_gif)
+    }
+
+    fn from_slice_truncated_color_table(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let (remaining, (header, color_table)) = Header::parse_lenient(bytes)?;
+
+        Ok(Self {
+            header,
+            global_color_table: color_table,
+            raw_block_data: remaining,
+        })
+    }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+/// How the area of a frame is treated before the next frame is drawn.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum DisposalMethod {
+    /// No disposal specified, the frame is left in place. This is also the disposal of
+    /// frames without Graphic Control Extension.
+    #[default]
+    Unspecified,
+    /// Leave the frame in place.
+    DoNotDispose,
+    /// Restore the area of the frame to the background color.
+    RestoreToBackground,
+    /// Restore the area of the frame to what was there before it was drawn.
+    RestoreToPrevious,
+    /// A reserved value, from 4 to 7, the frame is left in place.
+    Reserved(u8),
+}
+
+impl DisposalMethod {
+    fn from_flags(flags: u8) -> Self {
+        match (flags & 0b0001_1100) >> 2 {
+            0 => DisposalMethod::Unspecified,
+            1 => DisposalMethod::DoNotDispose,
+            2 => DisposalMethod::RestoreToBackground,
+            3 => DisposalMethod::RestoreToPrevious,
+            value => DisposalMethod::Reserved(value),
+        }
+    }
+
+    /// Returns `true` if the frame is left in place before the next frame is drawn.
+    pub fn keeps_frame(self) -> bool {
+        !matches!(
+            self,
+            DisposalMethod::RestoreToBackground | DisposalMethod::RestoreToPrevious
+        )
+    }
+}
+
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub struct GraphicControl {
     pub is_transparent: bool,
     pub transparent_color_index: u8,
     // centisecond
     pub delay_centis: u16,
+    pub disposal_method: DisposalMethod,
+    /// The animation should wait for user input before the next frame, or until the
+    /// delay elapses if it is not 0.
+    pub user_input: bool,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-
 pub struct ImageBlock<'a> {
     pub left: u16,
     pub top: u16,
@@ -227,8 +568,9 @@ pub struct ImageBlock<'a> {
     pub height: u16,
     pub is_interlaced: bool,
     pub lzw_min_code_size: u8,
-    local_color_table: Option<ColorTable<'a>>,
-    image_data: &'a [u8],
+    pub local_color_table: Option<ColorTable<'a>>,
+    /// LZW compressed image data, as len byte prefixed sub-blocks including the block terminator.
+    pub image_data: &'a [u8],
 }
 
 impl<'a> ImageBlock<'a> {
@@ -241,16 +583,20 @@ impl<'a> ImageBlock<'a> {
         let (input, flags) = take1(input)?;
         let is_interlaced = flags & 0b0100_0000 != 0;
         let has_local_color_table = flags & 0b1000_0000 != 0;
+        let is_sorted = flags & 0b0010_0000 != 0;
         let local_color_table_size = if has_local_color_table {
-            2_usize.pow(((flags & 0b0000_0111) + 1) as u32)
+            color_table_size(flags)
         } else {
             0
         };
+        if input.len() < local_color_table_size * 3 {
+            return Err(ParseError::TruncatedColorTable);
+        }
 
         let (input, local_color_table) = if local_color_table_size > 0 {
-            // Each color table entry is 4 bytes long
+            // Each color table entry is 3 bytes long
             let (input, table) = take_slice(input, local_color_table_size * 3)?;
-            (input, Some(ColorTable::new(table)))
+            (input, Some(ColorTable::new(table).with_sorted(is_sorted)))
         } else {
             (input, None)
         };
@@ -286,14 +632,41 @@ impl<'a> ImageBlock<'a> {
     }
 }
 
+/// Plain Text Extension, text to be rendered on a grid of character cells.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct PlainText<'a> {
+    /// Text grid position on the logical screen.
+    pub left: u16,
+    pub top: u16,
+    /// Text grid size in pixels.
+    pub width: u16,
+    pub height: u16,
+    /// Character cell size in pixels.
+    pub cell_width: u8,
+    pub cell_height: u8,
+    /// Color table index of the text.
+    pub fg_index: u8,
+    /// Color table index of the background.
+    pub bg_index: u8,
+    /// Len byte prefixed sub-blocks of the text, including the block terminator.
+    pub text: &'a [u8],
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum ExtensionBlock<'a> {
     GraphicControl(GraphicControl),
     Comment(&'a [u8]),
-    // TODO: handle PlainTextExtension
-    PlainText,
-    NetscapeApplication { repetitions: u16 },
-    Application, // ignore content
+    PlainText(PlainText<'a>),
+    NetscapeApplication {
+        repetitions: u16,
+    },
+    /// Application Extension other than the Netscape loop count, such as XMP or ICC data.
+    Application {
+        identifier: [u8; 8],
+        auth_code: [u8; 3],
+        /// Len byte prefixed sub-blocks of the application data, including the block terminator.
+        data: &'a [u8],
+    },
     Unknown(u8, &'a [u8]),
 }
 
@@ -303,10 +676,10 @@ impl<'a> ExtensionBlock<'a> {
         match ext_label {
             0xff => {
                 let (input, block_size_1) = take1(input)?;
-                let (input, app_id) = take::<8>(input)?;
-                let (input, app_auth_code) = take::<3>(input)?;
-                if block_size_1 == 11 && &app_id == b"NETSCAPE" && &app_auth_code == b"2.0" {
-                    let (input, block_size_2) = take1(input)?;
+                let (input, identifier) = take::<8>(input)?;
+                let (data, auth_code) = take::<3>(input)?;
+                if block_size_1 == 11 && &identifier == b"NETSCAPE" && &auth_code == b"2.0" {
+                    let (input, block_size_2) = take1(data)?;
                     if block_size_2 == 3 {
                         let (input, always_one) = take1(input)?;
                         if always_one == 1 {
@@ -321,17 +694,15 @@ impl<'a> ExtensionBlock<'a> {
                         }
                     }
                 }
-                let mut input0 = input;
-                loop {
-                    let (input, block_size_2) = take1(input0)?;
-                    if block_size_2 == 0 {
-                        input0 = input;
-                        break;
-                    }
-                    let (input, _data) = take_slice(input, block_size_2 as usize)?;
-                    input0 = input;
-                }
-                Ok((input0, ExtensionBlock::Application))
+                let input = eat_len_prefixed_subblocks(data)?;
+                Ok((
+                    input,
+                    ExtensionBlock::Application {
+                        identifier,
+                        auth_code,
+                        data: &data[..data.len() - input.len()],
+                    },
+                ))
             }
             0xfe => {
                 // Comment Extension
@@ -358,6 +729,8 @@ impl<'a> ExtensionBlock<'a> {
                 }
                 let (input, flags) = take1(input)?;
                 let is_transparent = flags & 0b0000_0001 != 0;
+                let user_input = flags & 0b0000_0010 != 0;
+                let disposal_method = DisposalMethod::from_flags(flags);
                 let (input, delay_centis) = le_u16(input)?;
                 let (input, transparent_color_index) = take1(input)?;
                 let (input, block_terminator) = take1(input)?;
@@ -371,15 +744,43 @@ impl<'a> ExtensionBlock<'a> {
                         is_transparent,
                         transparent_color_index,
                         delay_centis,
+                        disposal_method,
+                        user_input,
                     }),
                 ))
             }
             0x01 => {
-                let (input, _) = take::<13>(input)?;
-                let input = eat_len_prefixed_subblocks(input)?;
-                Ok((input, ExtensionBlock::PlainText))
+                // Plain Text Extension
+                let (input, block_size) = take1(input)?; // 12
+                if block_size != 12 {
+                    return Err(ParseError::InvalidConstSizeBytes);
+                }
+                let (input, left) = le_u16(input)?;
+                let (input, top) = le_u16(input)?;
+                let (input, width) = le_u16(input)?;
+                let (input, height) = le_u16(input)?;
+                let (input, cell_width) = take1(input)?;
+                let (input, cell_height) = take1(input)?;
+                let (input, fg_index) = take1(input)?;
+                let (text, bg_index) = take1(input)?;
+                let input = eat_len_prefixed_subblocks(text)?;
+
+                Ok((
+                    input,
+                    ExtensionBlock::PlainText(PlainText {
+                        left,
+                        top,
+                        width,
+                        height,
+                        cell_width,
+                        cell_height,
+                        fg_index,
+                        bg_index,
+                        text: &text[..text.len() - input.len()],
+                    }),
+                ))
             }
-            _ => unimplemented!(),
+            _ => Err(ParseError::UnsupportedExtension(ext_label)),
         }
     }
 }
@@ -414,7 +815,24 @@ impl<'a> Segment<'a> {
         }
     }
 
-    fn skip_to_next_graphic_control(mut input: &[u8]) -> Result<&[u8], ParseError> {
+    // Split the input after the trailer, into the segments up to and including the
+    // trailer, and the bytes after it. Segments before the trailer must parse.
+    fn split_after_trailer(input: &[u8]) -> Result<(&[u8], &[u8]), ParseError> {
+        let mut remain = input;
+        while remain.first() != Some(&0x3b) {
+            (remain, _) = Segment::parse(remain)?;
+        }
+        let len = input.len() - remain.len() + 1;
+        Ok((&input[..len], &input[len..]))
+    }
+
+    fn skip_to_next_graphic_control(input: &[u8]) -> Result<&[u8], ParseError> {
+        Self::skip_to_next_frame(input, false)
+    }
+
+    // Skip to the next Graphic Control Extension, or to the next Image Descriptor
+    // if `stop_at_image` is set, as images are not required to have a Graphic Control.
+    fn skip_to_next_frame(mut input: &[u8], stop_at_image: bool) -> Result<&[u8], ParseError> {
         loop {
             let (input0, ext_magic) = take1(input)?;
             if ext_magic == 0x21 {
@@ -433,9 +851,12 @@ impl<'a> Segment<'a> {
                     // Comment Extension
                     input = eat_len_prefixed_subblocks(input1)?;
                 } else {
-                    return Err(ParseError::InvalidExtensionLabel);
+                    return Err(ParseError::UnsupportedExtension(label));
                 }
             } else if ext_magic == 0x2c {
+                if stop_at_image {
+                    return Ok(input);
+                }
                 // Image Descriptor
                 // parse image, optional local color table
                 let (input1, _) = ImageBlock::parse(input0)?;
@@ -461,286 +882,3689 @@ impl<'a> Segment<'a> {
     }
 }
 
+/// Reads the segments of a GIF one by one, keeping the remaining input.
+///
+/// The reader stops after the trailer, or after the first segment that fails to parse.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-pub struct Gif<'a, C = Rgb888> {
-    raw_gif: RawGif<'a>,
-    color_type: PhantomData<C>,
+pub struct SegmentReader<'a> {
+    input: &'a [u8],
+    done: bool,
 }
 
-impl<'a, C> Gif<'a, C> {
-    pub fn from_slice(input: &'a [u8]) -> Result<Self, ParseError> {
-        let raw_gif = RawGif::from_slice(input)?;
-        Ok(Self {
-            raw_gif,
-            color_type: PhantomData,
-        })
+impl<'a> SegmentReader<'a> {
+    /// Reads the segments of `input`, the data following the header and the global color table.
+    pub const fn new(input: &'a [u8]) -> Self {
+        Self { input, done: false }
     }
 
-    pub fn frames(&'a self) -> FrameIterator<'a, C> {
-        FrameIterator::new(self)
+    /// Returns the input not read yet.
+    pub const fn remaining(&self) -> &'a [u8] {
+        self.input
     }
 
-    pub fn width(&self) -> u16 {
-        self.raw_gif.header.width
+    /// Parses the next segment, `None` is returned after the trailer or an error.
+    pub fn next_segment(&mut self) -> Option<Result<Segment<'a>, ParseError>> {
+        if self.done {
+            return None;
+        }
+        match Segment::parse(self.input) {
+            Ok((input, seg)) => {
+                self.input = input;
+                self.done = seg == Segment::Trailer;
+                Some(Ok(seg))
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
     }
+}
 
-    pub fn height(&self) -> u16 {
-        self.raw_gif.header.height
+impl<'a> Iterator for SegmentReader<'a> {
+    type Item = Result<Segment<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_segment()
     }
 }
 
-pub struct FrameIterator<'a, C> {
-    gif: &'a Gif<'a, C>,
-    frame_index: usize,
-    remain_raw_data: &'a [u8],
+/// Resource limits for `Gif::from_slice_checked`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// Maximum number of extensions and image blocks.
+    pub max_segments: usize,
+    /// Maximum number of pixels of all image blocks.
+    pub max_pixels: u64,
 }
 
-impl<'a, C> FrameIterator<'a, C> {
-    fn new(gif: &'a Gif<'a, C>) -> Self {
+impl Default for Limits {
+    /// 4096 segments and 16M pixels.
+    fn default() -> Self {
         Self {
-            gif,
-            frame_index: 0,
-            remain_raw_data: gif.raw_gif.raw_block_data,
+            max_segments: 4096,
+            max_pixels: 1 << 24,
         }
     }
 }
 
-impl<'a, C: PixelColor> Iterator for FrameIterator<'a, C> {
-    type Item = Frame<'a, C>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.remain_raw_data.is_empty() {
-            return None;
-        }
-
-        let input = self.remain_raw_data;
-        let input0 = Segment::skip_to_next_graphic_control(input).ok()?;
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Gif<'a, C = Rgb888> {
+    raw_gif: RawGif<'a>,
+    color_type: PhantomData<C>,
+}
 
-        let (input00, seg) = Segment::parse(input0).ok()?;
-        self.remain_raw_data = input00;
-
-        if let Segment::Extension(ExtensionBlock::GraphicControl(ctrl)) = seg {
-            let frame = Frame {
-                delay_centis: ctrl.delay_centis,
-                is_transparent: ctrl.is_transparent,
-                transparent_color_index: ctrl.transparent_color_index,
-                global_color_table: self.gif.raw_gif.global_color_table.clone(),
-                header: &self.gif.raw_gif.header,
-                raw_data: input00,
-                frame_index: self.frame_index,
-                _marker: PhantomData,
-            };
-            self.frame_index += 1;
-            return Some(frame);
-        } else {
-            None
-        }
+impl<'a, C> Gif<'a, C> {
+    pub fn from_slice(input: &'a [u8]) -> Result<Self, ParseError> {
+        let raw_gif = RawGif::from_slice(input)?;
+        Ok(Self {
+            raw_gif,
+            color_type: PhantomData,
+        })
     }
-}
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct Frame<'a, C> {
-    pub delay_centis: u16,
-    pub is_transparent: bool,
-    pub transparent_color_index: u8,
-    global_color_table: Option<ColorTable<'a>>,
-    header: &'a Header,
-    raw_data: &'a [u8],
-    frame_index: usize,
-    _marker: PhantomData<C>,
-}
+    /// Parses a GIF, ignoring any bytes after the trailer.
+    ///
+    /// `from_slice` keeps trailing bytes, which makes parsing the trailer fail with
+    /// `ParseError::JunkAfterTrailerByte`. Use this for files with data appended after the GIF.
+    pub fn from_slice_lenient(input: &'a [u8]) -> Result<Self, ParseError> {
+        let raw_gif = RawGif::from_slice_lenient(input)?;
+        Ok(Self {
+            raw_gif,
+            color_type: PhantomData,
+        })
+    }
 
-impl<'a, C> OriginDimensions for Frame<'a, C> {
-    fn size(&self) -> Size {
-        Size::new(self.header.width as _, self.header.height as _)
+    /// Parses a GIF, accepting a truncated global color table as `Header::parse_lenient`
+    /// does.
+    ///
+    /// The data ends within the table, so no frame is returned, but the dimensions of a
+    /// damaged file are still available, for example to show a placeholder.
+    pub fn from_slice_truncated_color_table(input: &'a [u8]) -> Result<Self, ParseError> {
+        let raw_gif = RawGif::from_slice_truncated_color_table(input)?;
+        Ok(Self {
+            raw_gif,
+            color_type: PhantomData,
+        })
     }
-}
 
-impl<'a, C> ImageDrawable for Frame<'a, C>
-where
-    C: PixelColor + From<Rgb888>,
-{
-    type Color = C;
+    /// Parses a GIF from untrusted input, checking the whole data against `limits`.
+    ///
+    /// All segments are parsed once, without decoding images. `ParseError::LimitExceeded`
+    /// is returned if the data has more segments or image pixels than allowed, which bounds
+    /// the work done by later decoding.
+    pub fn from_slice_checked(input: &'a [u8], limits: Limits) -> Result<Self, ParseError> {
+        let gif = Self::from_slice(input)?;
+        let mut segments = 0;
+        let mut pixels = 0;
+        for seg in gif.segments() {
+            match seg? {
+                Segment::Trailer => break,
+                Segment::Image(ImageBlock { width, height, .. }) => {
+                    pixels += u64::from(width) * u64::from(height);
+                }
+                _ => (),
+            }
+            segments += 1;
+            if segments > limits.max_segments || pixels > limits.max_pixels {
+                return Err(ParseError::LimitExceeded);
+            }
+        }
+        Ok(gif)
+    }
 
-    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
-    where
-        D: DrawTarget<Color = Self::Color>,
-    {
-        let mut input = self.raw_data;
-        while let Ok((input0, seg)) = Segment::parse(input) {
-            input = input0;
-            match seg {
-                Segment::Extension(ExtensionBlock::GraphicControl(_)) | Segment::Trailer => {
-                    // overflows to the next frame
-                    break;
+    /// Checks the structure of the whole data, without decoding images.
+    ///
+    /// `ParseError::InvalidImageDimensions` is returned if an image block exceeds the
+    /// logical screen. Such a GIF can still be drawn, pixels outside of the logical screen
+    /// are skipped.
+    ///
+    /// `ParseError::InvalidColorIndex` is returned if the transparent color index of a
+    /// frame is out of its color table, see `Frame::transparent_index_valid`.
+    pub fn validate(&self) -> Result<(), ParseError> {
+        let screen_width = u32::from(self.raw_gif.header.width);
+        let screen_height = u32::from(self.raw_gif.header.height);
+        let mut transparent_color_index = None;
+        for seg in self.segments() {
+            match seg? {
+                Segment::Extension(ExtensionBlock::GraphicControl(ctrl)) => {
+                    transparent_color_index =
+                        ctrl.is_transparent.then_some(ctrl.transparent_color_index);
                 }
                 Segment::Image(ImageBlock {
                     left,
                     top,
                     width,
-                    lzw_min_code_size,
+                    height,
                     local_color_table,
-                    image_data,
                     ..
                 }) => {
-                    let transparent_color_index = if self.is_transparent {
-                        Some(self.transparent_color_index)
-                    } else {
-                        None
-                    };
-                    let color_table = local_color_table
-                        .or_else(|| self.global_color_table.clone())
-                        .unwrap();
-                    let raw_image_data = LenPrefixRawDataView::new(image_data);
-                    let mut decoder = lzw::Decoder::new(raw_image_data, lzw_min_code_size);
-
-                    let mut idx: u32 = 0;
-
-                    while let Ok(Some(decoded)) = decoder.decode_next() {
-                        target.draw_iter(decoded.iter().filter_map(|&color_index| {
-                            if transparent_color_index == Some(color_index) {
-                                // skip drawing transparent color
-                                idx += 1;
-                                return None;
-                            }
-                            let x = left + (idx % u32::from(width)) as u16;
-                            let y = top + (idx / u32::from(width)) as u16;
-                            idx += 1;
-
-                            let color = color_table.get(color_index).unwrap();
-                            Some(Pixel(Point::new(x as i32, y as i32), color.into()))
-                        }))?;
+                    if u32::from(left) + u32::from(width) > screen_width
+                        || u32::from(top) + u32::from(height) > screen_height
+                    {
+                        return Err(ParseError::InvalidImageDimensions);
+                    }
+                    let color_table = local_color_table.or(self.raw_gif.global_color_table);
+                    if let (Some(index), Some(color_table)) = (transparent_color_index, color_table)
+                    {
+                        if usize::from(index) >= color_table.len() {
+                            return Err(ParseError::InvalidColorIndex);
+                        }
                     }
                 }
                 _ => (),
             }
         }
-
         Ok(())
     }
 
-    fn draw_sub_image<D>(
-        &self,
-        target: &mut D,
-        area: &embedded_graphics::primitives::Rectangle,
-    ) -> Result<(), D::Error>
-    where
-        D: DrawTarget<Color = Self::Color>,
-    {
-        let mut input = self.raw_data;
-        while let Ok((input0, seg)) = Segment::parse(input) {
-            input = input0;
-            match seg {
-                Segment::Extension(ExtensionBlock::GraphicControl(_)) | Segment::Trailer => {
-                    // overflows to the next frame
-                    break;
+    /// Returns the extension blocks in file order, without decoding images.
+    ///
+    /// The iterator stops at the trailer, or at the first segment that fails to parse.
+    pub fn extensions(&self) -> impl Iterator<Item = ExtensionBlock<'a>> {
+        self.segments()
+            .map_while(Result::ok)
+            .filter_map(|seg| match seg {
+                Segment::Extension(ext) => Some(ext),
+                _ => None,
+            })
+    }
+
+    /// Returns the text of the first Comment Extension, `None` if there is none.
+    ///
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD`, the text is truncated to 256 bytes.
+    /// heapless is a required dependency of the crate, so this is available without any
+    /// feature.
+    pub fn comment_string(&self) -> Option<heapless::String<256>> {
+        let data = self.extensions().find_map(|ext| match ext {
+            ExtensionBlock::Comment(data) => Some(data),
+            _ => None,
+        })?;
+        let mut bytes = heapless::Vec::<u8, 256>::new();
+        let mut sub_blocks = LenPrefixRawDataView::new(data);
+        bytes.extend(sub_blocks.by_ref().take(256));
+        let is_truncated = sub_blocks.next().is_some();
+
+        let mut string = heapless::String::new();
+        let mut input = &bytes[..];
+        while !input.is_empty() {
+            let (valid, invalid_len, replacement) = match core::str::from_utf8(input) {
+                Ok(valid) => (valid, 0, None),
+                Err(e) => {
+                    let valid = core::str::from_utf8(&input[..e.valid_up_to()]).unwrap_or("");
+                    match e.error_len() {
+                        Some(len) => (valid, len, Some(char::REPLACEMENT_CHARACTER)),
+                        // an incomplete sequence at the end may be cut by the truncation
+                        None => (
+                            valid,
+                            input.len() - valid.len(),
+                            (!is_truncated).then_some(char::REPLACEMENT_CHARACTER),
+                        ),
+                    }
                 }
-                Segment::Image(ImageBlock {
-                    left,
-                    top,
-                    width,
-                    lzw_min_code_size,
-                    local_color_table,
-                    image_data,
-                    ..
-                }) => {
-                    let transparent_color_index = if self.is_transparent {
-                        Some(self.transparent_color_index)
-                    } else {
-                        None
-                    };
-                    let color_table = local_color_table
-                        .or_else(|| self.global_color_table.clone())
-                        .unwrap();
-                    let raw_image_data = LenPrefixRawDataView::new(image_data);
-                    let mut decoder = lzw::Decoder::new(raw_image_data, lzw_min_code_size);
+            };
+            for c in valid.chars().chain(replacement) {
+                if string.push(c).is_err() {
+                    return Some(string);
+                }
+            }
+            input = &input[valid.len() + invalid_len..];
+        }
+        Some(string)
+    }
 
-                    let mut idx: u32 = 0;
+    /// Returns a reader of the segments following the header and the global color table.
+    pub fn segments(&self) -> SegmentReader<'a> {
+        SegmentReader::new(self.raw_gif.raw_block_data)
+    }
 
-                    while let Ok(Some(decoded)) = decoder.decode_next() {
-                        target.draw_iter(decoded.iter().filter_map(|&color_index| {
-                            if transparent_color_index == Some(color_index) {
-                                idx += 1;
-                                return None;
-                            }
+    /// Returns an iterator over the frames.
+    ///
+    /// The frames are counted the first time `size_hint` or `len` is called, by scanning
+    /// the whole data without decoding it.
+    pub fn frames(&'a self) -> FrameIterator<'a, C> {
+        self.counted_frames(usize::MAX)
+    }
 
-                            let x = left + (idx % u32::from(width)) as u16;
-                            let y = top + (idx / u32::from(width)) as u16;
-                            idx += 1;
+    fn counted_frames(&'a self, count_limit: usize) -> FrameIterator<'a, C> {
+        FrameIterator::new(
+            &self.raw_gif.header,
+            self.raw_gif.global_color_table,
+            self.raw_gif.raw_block_data,
+            count_limit,
+        )
+    }
 
-                            let pt = Point::new(x as i32, y as i32);
-                            if area.contains(pt) {
-                                let color = color_table.get(color_index).unwrap();
-                                Some(Pixel(pt, color.into()))
-                            } else {
-                                None
-                            }
-                        }))?;
-                    }
+    /// Returns at most the first `max` frames.
+    ///
+    /// The remaining frames are silently skipped, which bounds the work done on GIFs with
+    /// an unexpectedly large number of frames.
+    pub fn frames_limited(&'a self, max: usize) -> core::iter::Take<FrameIterator<'a, C>>
+    where
+        C: PixelColor,
+    {
+        self.counted_frames(max).take(max)
+    }
+
+    /// Returns every `step`th frame, starting with the first one.
+    ///
+    /// The delays of the skipped frames are added to the returned frame before them, so
+    /// the animation keeps its timing. Skipped frames are not drawn, which leaves missing
+    /// parts with GIFs drawing each frame on top of the previous one.
+    /// Panics if `step` is 0.
+    pub fn frames_stepped(&'a self, step: usize) -> SteppedFrameIterator<'a, C> {
+        assert!(step > 0, "step must be greater than 0");
+        SteppedFrameIterator {
+            frames: self.frames(),
+            step,
+        }
+    }
+
+    /// Returns the number of frames, by scanning the whole data.
+    pub fn frame_count(&'a self) -> usize
+    where
+        C: PixelColor,
+    {
+        self.frames().len()
+    }
+
+    /// Returns `true` if the GIF has more than one frame.
+    ///
+    /// Scanning stops at the second frame, unlike `frame_count`.
+    pub fn is_animated(&'a self) -> bool
+    where
+        C: PixelColor,
+    {
+        self.frames().nth(1).is_some()
+    }
+
+    /// Returns `true` if the GIF is an animation optimized as differences: the first frame
+    /// covers the logical screen, and the later frames are smaller updates drawn over the
+    /// previous ones, which are all left in place.
+    ///
+    /// Such an animation can be played by drawing the first frame fully, then only the
+    /// image blocks of the later frames, see `Frame::draw_dirty`.
+    pub fn is_optimized_diff(&'a self) -> bool
+    where
+        C: PixelColor,
+    {
+        let screen = Rectangle::new(Point::zero(), self.dimensions());
+        let covers_screen =
+            |frame: &Frame<'a, C>| frame.bounding_box().intersection(&screen) == screen;
+        let mut frames = self.frames();
+        let Some(first) = frames.next() else {
+            return false;
+        };
+        if !covers_screen(&first) {
+            return false;
+        }
+        let mut prev_disposal = first.disposal_method;
+        let mut is_animated = false;
+        for frame in frames {
+            if !prev_disposal.keeps_frame() || covers_screen(&frame) {
+                return false;
+            }
+            prev_disposal = frame.disposal_method;
+            is_animated = true;
+        }
+        is_animated
+    }
+
+    /// Returns the first frame, or `None` if the GIF contains no image.
+    ///
+    /// Images without a Graphic Control Extension, as in GIF87a files, are also
+    /// returned as a frame, with no delay and no transparency.
+    pub fn first_frame(&'a self) -> Option<Frame<'a, C>>
+    where
+        C: PixelColor,
+    {
+        self.frames().next()
+    }
+
+    /// Decodes the first frame into `out`, a `tw` x `th` row-major thumbnail of the logical
+    /// screen, using nearest-neighbor sampling.
+    ///
+    /// Colors are only looked up for the sampled pixels. Transparent pixels are skipped,
+    /// leaving `out` untouched. `ParseError::BufferTooSmall` is returned if `out` is not
+    /// `tw * th` long, and `ParseError::MissingImageBlock` if the GIF contains no image.
+    pub fn thumbnail(&'a self, out: &mut [Rgb888], tw: usize, th: usize) -> Result<(), ParseError>
+    where
+        C: PixelColor,
+    {
+        if out.len() != tw * th {
+            return Err(ParseError::BufferTooSmall);
+        }
+        let frame = self.first_frame().ok_or(ParseError::MissingImageBlock)?;
+        let width = usize::from(self.width());
+        let height = usize::from(self.height());
+        // thumbnail coordinates sampling `v`, the ones mapped back to it
+        let sampling = |v: u16, len: usize, tlen: usize| {
+            let v = usize::from(v);
+            (v * tlen).div_ceil(len)..((v + 1) * tlen).div_ceil(len)
+        };
+        let transparent_color_index = if frame.is_transparent {
+            Some(frame.transparent_color_index)
+        } else {
+            None
+        };
+
+        let mut invalid_color_index = false;
+        let mut pixels = frame.index_pixels();
+        while let Some((x, y, color_index)) = pixels.next() {
+            let (xs, ys) = (sampling(x, width, tw), sampling(y, height, th));
+            if xs.is_empty() || ys.is_empty() || transparent_color_index == Some(color_index) {
+                continue;
+            }
+            let Some(color_table) = pixels.local_color_table().or(frame.global_color_table) else {
+                return Err(ParseError::MissingColorTable);
+            };
+            let Some(color) = color_table.get(color_index) else {
+                invalid_color_index = true;
+                continue;
+            };
+            for ty in ys {
+                out[ty * tw..][xs.clone()].fill(color);
+            }
+        }
+
+        match pixels.error {
+            Some(e) => Err(e),
+            None if invalid_color_index => Err(ParseError::InvalidColorIndex),
+            None => Ok(()),
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.raw_gif.header.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.raw_gif.header.height
+    }
+
+    /// Returns the size of the logical screen.
+    pub fn dimensions(&self) -> Size {
+        Size::new(self.width() as u32, self.height() as u32)
+    }
+
+    /// Returns the global color table, if any.
+    pub fn global_color_table(&self) -> Option<ColorTable<'a>> {
+        self.raw_gif.global_color_table
+    }
+
+    /// Returns the version of the GIF.
+    ///
+    /// GIF87a predates extensions, such a GIF has no transparency, no frame delays and no
+    /// loop count, it can be drawn as a still image.
+    pub fn version(&self) -> Version {
+        self.raw_gif.header.version
+    }
+
+    /// Returns the loop count of the Netscape Application Extension, `0` meaning forever.
+    ///
+    /// `None` is returned if there is no such extension before the first image, which is
+    /// always the case for GIF87a as it predates extensions. Such GIFs are played once.
+    pub fn loop_count(&self) -> Option<u16> {
+        if self.raw_gif.header.version == Version::V87a {
+            return None;
+        }
+        let mut input = self.raw_gif.raw_block_data;
+        loop {
+            let (input0, seg) = Segment::parse(input).ok()?;
+            input = input0;
+            match seg {
+                Segment::Extension(ExtensionBlock::NetscapeApplication { repetitions }) => {
+                    return Some(repetitions)
                 }
-                _ => (),
+                Segment::Extension(_) => (),
+                _ => return None,
             }
         }
+    }
+}
 
-        Ok(())
+impl<C> OriginDimensions for Gif<'_, C> {
+    fn size(&self) -> Size {
+        self.dimensions()
     }
 }
 
-impl<C> fmt::Debug for Frame<'_, C> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Frame")
-            .field("frame_index", &self.frame_index)
-            .field("delay_centis", &self.delay_centis)
-            .field("is_transparent", &self.is_transparent)
-            .field("transparent_color_index", &self.transparent_color_index)
-            .field("len(remain_data)", &self.raw_data.len())
-            .finish()
+/// A GIF owning a copy of its data, to be stored without borrowing the input.
+///
+/// Only offsets into the data are kept, the frames are parsed by `frames()` like with `Gif`.
+#[cfg(feature = "alloc")]
+pub struct OwnedGif<C = Rgb888> {
+    data: alloc::vec::Vec<u8>,
+    header: Header,
+    // the global color table is stored between the header and the blocks
+    global_color_table_sorted: bool,
+    // offset of the blocks following the header
+    raw_block_data: usize,
+    color_type: PhantomData<C>,
+}
+
+#[cfg(feature = "alloc")]
+impl<C> OwnedGif<C> {
+    /// Parses a GIF and copies its data.
+    pub fn from_slice(input: &[u8]) -> Result<Self, ParseError> {
+        let raw_gif = RawGif::from_slice(input)?;
+        Ok(Self {
+            data: input.into(),
+            header: raw_gif.header,
+            global_color_table_sorted: raw_gif
+                .global_color_table
+                .is_some_and(|table| table.is_sorted()),
+            raw_block_data: input.len() - raw_gif.raw_block_data.len(),
+            color_type: PhantomData,
+        })
+    }
+
+    pub fn frames(&self) -> FrameIterator<'_, C> {
+        self.counted_frames(usize::MAX)
+    }
+
+    fn counted_frames(&self, count_limit: usize) -> FrameIterator<'_, C> {
+        FrameIterator::new(
+            &self.header,
+            self.global_color_table(),
+            &self.data[self.raw_block_data..],
+            count_limit,
+        )
+    }
+
+    /// Returns at most the first `max` frames, the remaining frames are silently skipped.
+    pub fn frames_limited(&self, max: usize) -> core::iter::Take<FrameIterator<'_, C>>
+    where
+        C: PixelColor,
+    {
+        self.counted_frames(max).take(max)
+    }
+
+    pub fn frame_count(&self) -> usize
+    where
+        C: PixelColor,
+    {
+        self.frames().len()
+    }
+
+    /// Returns `true` if the GIF has more than one frame.
+    pub fn is_animated(&self) -> bool
+    where
+        C: PixelColor,
+    {
+        self.frames().nth(1).is_some()
+    }
+
+    pub fn first_frame(&self) -> Option<Frame<'_, C>>
+    where
+        C: PixelColor,
+    {
+        self.frames().next()
+    }
+
+    pub fn width(&self) -> u16 {
+        self.header.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.header.height
+    }
+
+    pub fn global_color_table(&self) -> Option<ColorTable<'_>> {
+        // the Logical Screen Descriptor is 13 bytes long
+        self.header.has_global_color_table.then(|| {
+            ColorTable::new(&self.data[13..self.raw_block_data])
+                .with_sorted(self.global_color_table_sorted)
+        })
     }
 }
 
-#[cfg(feature = "defmt")]
-impl<C> defmt::Format for Frame<'_, C> {
-    fn format(&self, f: defmt::Formatter) {
-        defmt::write!(
-            f,
-            "Frame {{ frame_index: {}, delay_centis: {} remain: {}}}",
-            self.frame_index,
-            self.delay_centis,
-            self.raw_data.len()
-        );
+#[cfg(feature = "alloc")]
+impl<C> OriginDimensions for OwnedGif<C> {
+    fn size(&self) -> Size {
+        Size::new(self.width() as u32, self.height() as u32)
     }
 }
 
-/// Parse error.
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
-pub enum ParseError {
-    /// The image uses an unsupported bit depth.
-    UnsupportedBpp(u16),
+pub struct FrameIterator<'a, C> {
+    header: &'a Header,
+    global_color_table: Option<ColorTable<'a>>,
+    frame_index: usize,
+    // frames yielded from the first one, counted up to `count_limit` by the first call to
+    // `size_hint`
+    frame_count: Cell<Option<usize>>,
+    count_limit: usize,
+    // blocks of the whole GIF, to restart from the first frame
+    raw_block_data: &'a [u8],
+    remain_raw_data: &'a [u8],
+    _marker: PhantomData<C>,
+}
 
-    /// Unexpected end of file.
-    UnexpectedEndOfFile,
+impl<'a, C> FrameIterator<'a, C> {
+    // The `size_hint` of the iterator is only exact for the first `count_limit` frames,
+    // which bounds the scan of `frames_limited`.
+    fn new(
+        header: &'a Header,
+        global_color_table: Option<ColorTable<'a>>,
+        raw_block_data: &'a [u8],
+        count_limit: usize,
+    ) -> Self {
+        Self {
+            header,
+            global_color_table,
+            frame_index: 0,
+            frame_count: Cell::new(None),
+            count_limit,
+            raw_block_data,
+            remain_raw_data: raw_block_data,
+            _marker: PhantomData,
+        }
+    }
 
-    /// Invalid file signatures.
-    InvalidFileSignature([u8; 3]),
+    // Number of frames from the first one, up to `count_limit`.
+    fn frame_count(&self) -> usize {
+        if let Some(frame_count) = self.frame_count.get() {
+            return frame_count;
+        }
+        let mut scan = self.clone();
+        scan.reset();
+        let mut frame_count = 0;
+        while frame_count < self.count_limit && scan.next_raw().is_some() {
+            frame_count += 1;
+        }
+        self.frame_count.set(Some(frame_count));
+        frame_count
+    }
 
-    /// Unsupported compression method.
-    UnsupportedCompressionMethod(u32),
+    // Moves past the next frame, returning its Graphic Control and its raw data.
+    fn next_raw(&mut self) -> Option<(GraphicControl, &'a [u8])> {
+        if self.remain_raw_data.is_empty() {
+            return None;
+        }
 
-    /// Unsupported header length.
-    UnsupportedHeaderLength(u32),
+        let input = self.remain_raw_data;
+        let input0 = Segment::skip_to_next_frame(input, true).ok()?;
 
-    /// Unsupported channel masks.
-    UnsupportedChannelMasks,
+        let (input00, seg) = Segment::parse(input0).ok()?;
 
-    /// Invalid image dimensions.
-    InvalidImageDimensions,
+        let (ctrl, raw_data) = match seg {
+            Segment::Extension(ExtensionBlock::GraphicControl(ctrl)) => (ctrl, input00),
+            // image without a Graphic Control, draw from the Image Descriptor
+            Segment::Image(_) => (GraphicControl::default(), input0),
+            _ => return None,
+        };
+        // skip image blocks belonging to this frame
+        self.remain_raw_data = Segment::skip_to_next_graphic_control(input00).unwrap_or(&[]);
+        Some((ctrl, raw_data))
+    }
 
-    InvalidByte,
+    /// Rewinds to the first frame, for looping playback.
+    pub fn reset(&mut self) {
+        self.frame_index = 0;
+        self.remain_raw_data = self.raw_block_data;
+    }
+}
 
-    JunkAfterTrailerByte,
+impl<C> Clone for FrameIterator<'_, C> {
+    fn clone(&self) -> Self {
+        Self {
+            header: self.header,
+            global_color_table: self.global_color_table,
+            frame_index: self.frame_index,
+            frame_count: self.frame_count.clone(),
+            count_limit: self.count_limit,
+            raw_block_data: self.raw_block_data,
+            remain_raw_data: self.remain_raw_data,
+            _marker: PhantomData,
+        }
+    }
+}
 
-    /// Current size bytes should be a constant.
-    InvalidConstSizeBytes,
+impl<'a, C: PixelColor> Iterator for FrameIterator<'a, C> {
+    type Item = Frame<'a, C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (ctrl, raw_data) = self.next_raw()?;
+        let frame = Frame {
+            delay_centis: ctrl.delay_centis,
+            is_transparent: ctrl.is_transparent,
+            transparent_color_index: ctrl.transparent_color_index,
+            disposal_method: ctrl.disposal_method,
+            user_input: ctrl.user_input,
+            global_color_table: self.global_color_table,
+            header: self.header,
+            raw_data,
+            frame_index: self.frame_index,
+            _marker: PhantomData,
+        };
+        self.frame_index += 1;
+        Some(frame)
+    }
+
+    /// Returns the exact number of remaining frames, the frames are counted by the first
+    /// call.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.frame_count().saturating_sub(self.frame_index);
+        (remaining, Some(remaining))
+    }
+}
 
-    InvalidExtensionLabel,
+impl<C: PixelColor> ExactSizeIterator for FrameIterator<'_, C> {}
+
+/// Iterator over every nth frame, see `Gif::frames_stepped`.
+#[derive(Clone)]
+pub struct SteppedFrameIterator<'a, C> {
+    frames: FrameIterator<'a, C>,
+    step: usize,
+}
+
+impl<'a, C: PixelColor> Iterator for SteppedFrameIterator<'a, C> {
+    type Item = Frame<'a, C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut frame = self.frames.next()?;
+        for skipped in self.frames.by_ref().take(self.step - 1) {
+            frame.delay_centis = frame.delay_centis.saturating_add(skipped.delay_centis);
+        }
+        Some(frame)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Frame<'a, C> {
+    pub delay_centis: u16,
+    pub is_transparent: bool,
+    /// Color index of the transparent pixels if `is_transparent`.
+    ///
+    /// An index out of the color table never matches a pixel, so no pixel is skipped when
+    /// drawing, see `transparent_index_valid`.
+    pub transparent_color_index: u8,
+    pub disposal_method: DisposalMethod,
+    /// The player should wait for user input before the next frame, see `GraphicControl`.
+    pub user_input: bool,
+    global_color_table: Option<ColorTable<'a>>,
+    header: &'a Header,
+    raw_data: &'a [u8],
+    frame_index: usize,
+    _marker: PhantomData<C>,
+}
+
+impl<'a, C> Frame<'a, C> {
+    /// Returns the first image block of this frame, with its raw LZW compressed data.
+    ///
+    /// `ParseError::MissingImageBlock` is returned if the frame contains no image block.
+    pub fn image_block(&self) -> Result<ImageBlock<'a>, ParseError> {
+        let mut input = self.raw_data;
+        loop {
+            let (input0, seg) = Segment::parse(input)?;
+            input = input0;
+            match seg {
+                Segment::Extension(ExtensionBlock::GraphicControl(_)) | Segment::Trailer => {
+                    return Err(ParseError::MissingImageBlock)
+                }
+                Segment::Image(image_block) => return Ok(image_block),
+                _ => (),
+            }
+        }
+    }
+
+    /// Returns false if the frame is transparent and its transparent color index is out of
+    /// the color table of its first image block, which often signals a malformed file.
+    pub fn transparent_index_valid(&self) -> bool {
+        if !self.is_transparent {
+            return true;
+        }
+        self.image_block()
+            .ok()
+            .and_then(|image_block| image_block.local_color_table.or(self.global_color_table))
+            .is_some_and(|color_table| {
+                usize::from(self.transparent_color_index) < color_table.len()
+            })
+    }
+
+    /// Returns the area of the logical screen touched by this frame.
+    ///
+    /// This is the rectangle of the first image block in the frame, which can be smaller
+    /// than the logical screen returned by `size()`. A zero sized rectangle is returned
+    /// if the frame contains no image block.
+    pub fn bounding_box(&self) -> Rectangle {
+        match self.image_block().ok() {
+            Some(ImageBlock {
+                left,
+                top,
+                width,
+                height,
+                ..
+            }) => Rectangle::new(
+                Point::new(left as i32, top as i32),
+                Size::new(width as u32, height as u32),
+            ),
+            None => Rectangle::zero(),
+        }
+    }
+
+    /// Returns the left position of the first image block on the logical screen.
+    ///
+    /// `0` is returned if the frame contains no image block, as for the other
+    /// `frame_*` methods.
+    pub fn frame_left(&self) -> u16 {
+        self.image_block().map_or(0, |image_block| image_block.left)
+    }
+
+    /// Returns the top position of the first image block on the logical screen.
+    pub fn frame_top(&self) -> u16 {
+        self.image_block().map_or(0, |image_block| image_block.top)
+    }
+
+    /// Returns the width of the first image block, unlike `size()` which returns the
+    /// logical screen size.
+    pub fn frame_width(&self) -> u16 {
+        self.image_block()
+            .map_or(0, |image_block| image_block.width)
+    }
+
+    /// Returns the height of the first image block.
+    pub fn frame_height(&self) -> u16 {
+        self.image_block()
+            .map_or(0, |image_block| image_block.height)
+    }
+
+    /// Returns true if this frame covers the whole logical screen without transparency.
+    ///
+    /// Such a frame hides everything drawn before it, so the disposal of the previous
+    /// frame can be skipped.
+    pub fn is_full_opaque(&self) -> bool {
+        !self.is_transparent && self.bounding_box() == Rectangle::new(Point::zero(), self.size())
+    }
+
+    /// Returns true if the first image block of this frame is interlaced.
+    ///
+    /// The image block is parsed on each call.
+    pub fn is_interlaced(&self) -> Result<bool, ParseError> {
+        self.image_block()
+            .map(|image_block| image_block.is_interlaced)
+    }
+
+    /// Returns the LZW minimum code size of the first image block of this frame.
+    ///
+    /// The image block is parsed on each call.
+    pub fn lzw_min_code_size(&self) -> Result<u8, ParseError> {
+        self.image_block()
+            .map(|image_block| image_block.lzw_min_code_size)
+    }
+
+    /// Returns the Graphic Control Extension fields of this frame.
+    ///
+    /// The default is returned for an image without Graphic Control Extension.
+    pub fn graphic_control(&self) -> GraphicControl {
+        GraphicControl {
+            is_transparent: self.is_transparent,
+            transparent_color_index: self.transparent_color_index,
+            delay_centis: self.delay_centis,
+            disposal_method: self.disposal_method,
+            user_input: self.user_input,
+        }
+    }
+
+    /// Returns the frame delay as a `Duration`.
+    pub fn delay(&self) -> Duration {
+        Duration::from_millis(u64::from(self.delay_centis) * 10)
+    }
+
+    /// Returns the frame delay in centiseconds, at least `min`.
+    ///
+    /// Many GIFs have a delay of 0 or 1, which browsers play at a default delay of
+    /// about 10 centiseconds instead. The raw value is kept in `delay_centis`.
+    pub fn effective_delay_centis(&self, min: u16) -> u16 {
+        self.delay_centis.max(min)
+    }
+
+    /// Returns the color table used by this frame.
+    ///
+    /// This is the local color table of the frame if present, else the global color table.
+    pub fn color_table(&self) -> Option<ColorTable<'a>> {
+        self.image_block()
+            .ok()
+            .and_then(|image_block| image_block.local_color_table)
+            .or(self.global_color_table)
+    }
+
+    /// Returns the number of entries in the color table used by this frame.
+    ///
+    /// This is the local color table of the frame if present, else the global color table.
+    pub fn color_count(&self) -> usize {
+        self.color_table().map_or(0, |table| table.len())
+    }
+
+    /// Returns the number of distinct color indices used by the pixels of this frame.
+    ///
+    /// This requires a full decode pass of the frame. The transparent color index is not
+    /// counted, decoding stops at malformed image data.
+    pub fn used_color_count(&self) -> usize {
+        let transparent_color_index = if self.is_transparent {
+            Some(self.transparent_color_index)
+        } else {
+            None
+        };
+        let mut used = [0u64; 4];
+
+        let mut input = self.raw_data;
+        while let Ok((input0, seg)) = Segment::parse(input) {
+            input = input0;
+            match seg {
+                Segment::Extension(ExtensionBlock::GraphicControl(_)) | Segment::Trailer => break,
+                Segment::Image(image_block) => {
+                    let raw_image_data = LenPrefixRawDataView::new(image_block.image_data);
+                    let mut decoder =
+                        lzw::Decoder::new(raw_image_data, image_block.lzw_min_code_size);
+                    let mut remaining = image_block.width as usize * image_block.height as usize;
+                    while let Ok(Some(decoded)) = decoder.decode_next() {
+                        for &color_index in decoded.iter().take(remaining) {
+                            used[color_index as usize / 64] |= 1 << (color_index % 64);
+                        }
+                        remaining = remaining.saturating_sub(decoded.len());
+                        if remaining == 0 {
+                            break;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(index) = transparent_color_index {
+            used[index as usize / 64] &= !(1 << (index % 64));
+        }
+        used.iter().map(|bits| bits.count_ones() as usize).sum()
+    }
+
+    /// Decodes the color indices of this frame into `out`, a row-major buffer of the logical screen.
+    ///
+    /// Image blocks are placed at their position on the logical screen, interlaced images
+    /// are deinterlaced. Transparent pixels are skipped, leaving `out` untouched.
+    /// `ParseError::BufferTooSmall` is returned if `out` can't hold the logical screen.
+    pub fn decode_indices(&self, out: &mut [u8]) -> Result<(), ParseError> {
+        let screen_width = self.header.width as usize;
+        let screen_height = self.header.height as usize;
+        if out.len() < screen_width * screen_height {
+            return Err(ParseError::BufferTooSmall);
+        }
+        let transparent_color_index = if self.is_transparent {
+            Some(self.transparent_color_index)
+        } else {
+            None
+        };
+
+        let mut input = self.raw_data;
+        loop {
+            let (input0, seg) = Segment::parse(input)?;
+            input = input0;
+            match seg {
+                Segment::Extension(ExtensionBlock::GraphicControl(_)) | Segment::Trailer => break,
+                Segment::Image(image_block) if image_block.width > 0 => {
+                    let raw_image_data = LenPrefixRawDataView::new(image_block.image_data);
+                    let mut decoder =
+                        lzw::Decoder::new(raw_image_data, image_block.lzw_min_code_size);
+
+                    let mut cursor = BlockCursor::new(&image_block);
+                    while let Some(decoded) = decoder.decode_next()? {
+                        // codes past the last pixel are ignored, such as padding bits
+                        // decoded as codes when the end code is missing
+                        for &color_index in decoded.iter().take(cursor.remaining()) {
+                            let (x, y) = cursor.advance();
+                            if transparent_color_index == Some(color_index) {
+                                continue;
+                            }
+                            let (x, y) = (x as usize, y as usize);
+                            if x < screen_width && y < screen_height {
+                                out[y * screen_width + x] = color_index;
+                            }
+                        }
+                        if cursor.remaining() == 0 {
+                            break;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an iterator over the decoded pixels of this frame.
+    ///
+    /// Pixels are positioned on the logical screen, transparent pixels are skipped.
+    pub fn pixels(&self) -> PixelIterator<'a, C> {
+        PixelIterator {
+            indices: self.index_pixels(),
+            global_color_table: self.global_color_table,
+            transparent_color_index: if self.is_transparent {
+                Some(self.transparent_color_index)
+            } else {
+                None
+            },
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the color indices of this frame, as `(x, y, index)`.
+    ///
+    /// Unlike `pixels`, colors are not looked up and transparent pixels are yielded too,
+    /// for sinks not using embedded-graphics. Pixels are positioned on the logical screen,
+    /// interlaced images are deinterlaced. Decoding stops at malformed image data.
+    pub fn index_pixels(&self) -> IndexPixelIterator<'a> {
+        IndexPixelIterator {
+            remain_raw_data: self.raw_data,
+            screen_width: self.header.width,
+            screen_height: self.header.height,
+            image: None,
+            error: None,
+        }
+    }
+
+    /// Returns a 32-bit FNV-1a hash of the decoded color indices, to detect changed frames
+    /// in render caches.
+    ///
+    /// The area of the first image block is hashed with the indices, colors are not.
+    /// Decoding stops at malformed image data, the indices decoded so far are hashed.
+    pub fn content_hash(&self) -> u32 {
+        const FNV_OFFSET_BASIS: u32 = 0x811c_9dc5;
+        const FNV_PRIME: u32 = 0x0100_0193;
+
+        let fnv = |hash: u32, byte: u8| (hash ^ u32::from(byte)).wrapping_mul(FNV_PRIME);
+        let area = self.bounding_box();
+        let geometry = [
+            area.top_left.x as u16,
+            area.top_left.y as u16,
+            area.size.width as u16,
+            area.size.height as u16,
+        ];
+        let hash = geometry
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .fold(FNV_OFFSET_BASIS, fnv);
+        self.index_pixels()
+            .fold(hash, |hash, (_, _, color_index)| fnv(hash, color_index))
+    }
+
+    /// Decodes the color indices of this frame into `out`, packed two per byte for 16-color
+    /// framebuffers.
+    ///
+    /// Rows of the logical screen are `(width + 1) / 2` bytes long, the pixel at an even `x`
+    /// in the high nibble, the next one in the low nibble. Transparent pixels are skipped,
+    /// leaving their nibble untouched. `ParseError::InvalidColorIndex` is returned for an
+    /// index above 15, and `ParseError::BufferTooSmall` if `out` can't hold the logical screen.
+    pub fn decode_indices_4bpp(&self, out: &mut [u8]) -> Result<(), ParseError> {
+        let stride = (self.header.width as usize).div_ceil(2);
+        if out.len() < stride * self.header.height as usize {
+            return Err(ParseError::BufferTooSmall);
+        }
+        let transparent_color_index = if self.is_transparent {
+            Some(self.transparent_color_index)
+        } else {
+            None
+        };
+
+        let mut pixels = self.index_pixels();
+        for (x, y, color_index) in pixels.by_ref() {
+            if transparent_color_index == Some(color_index) {
+                continue;
+            }
+            if color_index > 0xf {
+                return Err(ParseError::InvalidColorIndex);
+            }
+            let i = y as usize * stride + x as usize / 2;
+            let shift = if x % 2 == 0 { 4 } else { 0 };
+            out[i] = out[i] & !(0xf << shift) | color_index << shift;
+        }
+        pixels.error.map_or(Ok(()), Err)
+    }
+
+    /// Decodes this frame into `out`, a row-major buffer of R, G, B bytes of the logical screen.
+    ///
+    /// Image blocks are placed at their position on the logical screen, interlaced images
+    /// are deinterlaced. Transparent pixels are skipped, leaving `out` untouched.
+    /// `ParseError::BufferTooSmall` is returned if `out` can't hold the logical screen.
+    pub fn decode_rgb888(&self, out: &mut [u8]) -> Result<(), ParseError> {
+        let screen_width = self.header.width as usize;
+        if out.len() < 3 * screen_width * self.header.height as usize {
+            return Err(ParseError::BufferTooSmall);
+        }
+        let transparent_color_index = if self.is_transparent {
+            Some(self.transparent_color_index)
+        } else {
+            None
+        };
+
+        let mut invalid_color_index = false;
+        let mut pixels = self.index_pixels();
+        while let Some((x, y, color_index)) = pixels.next() {
+            if transparent_color_index == Some(color_index) {
+                continue;
+            }
+            let Some(color_table) = pixels.local_color_table().or(self.global_color_table) else {
+                return Err(ParseError::MissingColorTable);
+            };
+            let Some(color) = color_table.get(color_index) else {
+                invalid_color_index = true;
+                continue;
+            };
+            let i = 3 * (y as usize * screen_width + x as usize);
+            out[i..i + 3].copy_from_slice(&[color.r(), color.g(), color.b()]);
+        }
+
+        match pixels.error {
+            Some(e) => Err(e),
+            None if invalid_color_index => Err(ParseError::InvalidColorIndex),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns an iterator over the rows of color indices of the first image block.
+    ///
+    /// Frames with several image blocks only yield the rows of the first one. Rows are
+    /// yielded as `(y, indices)`, with `y` on the logical screen and the first index at
+    /// the left edge of `bounding_box()`. Interlaced rows are yielded in decoding order.
+    /// Rows past `u16::MAX`, outside of any logical screen, are yielded with `y` saturated
+    /// to `u16::MAX`. `N` is the capacity of the row buffer, `ParseError::BufferTooSmall`
+    /// is returned if the image block is wider.
+    ///
+    /// Malformed or truncated image data yields an error instead of the partial row, and
+    /// ends the iteration.
+    pub fn rows<const N: usize>(&self) -> Result<RowIterator<'a, N>, ParseError> {
+        let image_block = self.image_block()?;
+        let width = image_block.width as usize;
+        if width > N {
+            return Err(ParseError::BufferTooSmall);
+        }
+        let raw_image_data = LenPrefixRawDataView::new(image_block.image_data);
+        Ok(RowIterator {
+            decoder: Some(lzw::Decoder::new(
+                raw_image_data,
+                image_block.lzw_min_code_size,
+            )),
+            top: image_block.top,
+            width,
+            height: image_block.height as usize,
+            is_interlaced: image_block.is_interlaced,
+            row: heapless::Vec::new(),
+            cursor: 0,
+            n: 0,
+        })
+    }
+
+    /// Returns a decoder of the color indices of the first image block, pumped one chunk
+    /// at a time with `FrameDecoder::decode_next`.
+    pub fn decoder(&self) -> Result<FrameDecoder<'a>, ParseError> {
+        let image_block = self.image_block()?;
+        let raw_image_data = LenPrefixRawDataView::new(image_block.image_data);
+        Ok(FrameDecoder {
+            decoder: lzw::Decoder::new(raw_image_data, image_block.lzw_min_code_size),
+            left: image_block.left,
+            top: image_block.top,
+            width: image_block.width as usize,
+            height: image_block.height as usize,
+            is_interlaced: image_block.is_interlaced,
+            idx: 0,
+        })
+    }
+}
+
+/// Decoder of the color indices of an image block, see `Frame::decoder`.
+pub struct FrameDecoder<'a> {
+    decoder: lzw::Decoder<LenPrefixRawDataView<'a>>,
+    left: u16,
+    top: u16,
+    width: usize,
+    height: usize,
+    is_interlaced: bool,
+    // index of the next pixel in the image block
+    idx: usize,
+}
+
+/// Color indices decoded by `FrameDecoder::decode_next`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DecodedChunk<'d> {
+    /// Column of the first index on the logical screen.
+    pub x: u16,
+    /// Row of the first index on the logical screen.
+    pub y: u16,
+    pub indices: &'d [u8],
+}
+
+impl FrameDecoder<'_> {
+    /// Decodes the next chunk of color indices, `None` is returned once the image block is
+    /// complete.
+    ///
+    /// The chunk starts at `(x, y)` on the logical screen and follows the decoding order,
+    /// it can wrap to the next rows of the image block. Rows of an interlaced image are
+    /// decoded out of order, the next row of a chunk is the next one in interlaced order.
+    /// `ParseError::UnexpectedEndOfFile` is returned if the data ends before the last pixel.
+    pub fn decode_next(&mut self) -> Result<Option<DecodedChunk<'_>>, ParseError> {
+        let n_pixels = self.width * self.height;
+        loop {
+            if self.idx >= n_pixels {
+                return Ok(None);
+            }
+            match self.decoder.decode_next()? {
+                None => return Err(ParseError::UnexpectedEndOfFile),
+                // clear codes decode no index
+                Some([]) => (),
+                Some(_) => break,
+            }
+        }
+
+        let (col, row) = (self.idx % self.width, self.idx / self.width);
+        let row = if self.is_interlaced {
+            interlaced_row(row, self.height)
+        } else {
+            row
+        };
+        let decoded = self.decoder.buffer();
+        // codes past the last pixel are ignored
+        let decoded = &decoded[..decoded.len().min(n_pixels - self.idx)];
+        self.idx += decoded.len();
+        Ok(Some(DecodedChunk {
+            x: self.left.saturating_add(col as u16),
+            y: self.top.saturating_add(row as u16),
+            indices: decoded,
+        }))
+    }
+}
+
+/// Iterator over the rows of an image block, see `Frame::rows`.
+pub struct RowIterator<'a, const N: usize> {
+    decoder: Option<lzw::Decoder<LenPrefixRawDataView<'a>>>,
+    top: u16,
+    width: usize,
+    height: usize,
+    is_interlaced: bool,
+    row: heapless::Vec<u8, N>,
+    // position in the decoder buffer
+    cursor: usize,
+    // number of rows yielded
+    n: usize,
+}
+
+impl<const N: usize> Iterator for RowIterator<'_, N> {
+    type Item = Result<(u16, heapless::Vec<u8, N>), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.n >= self.height || self.width == 0 {
+            return None;
+        }
+        let decoder = self.decoder.as_mut()?;
+        // LZW output is not aligned to rows, accumulate until the row is complete
+        self.row.clear();
+        while self.row.len() < self.width {
+            let buffered = decoder.buffer().get(self.cursor..).unwrap_or(&[]);
+            if buffered.is_empty() {
+                let error = match decoder.decode_next() {
+                    Ok(Some(_)) => {
+                        self.cursor = 0;
+                        continue;
+                    }
+                    Ok(None) => ParseError::UnexpectedEndOfFile,
+                    Err(e) => e,
+                };
+                // malformed or truncated data, the partial row is dropped
+                self.decoder = None;
+                return Some(Err(error));
+            }
+            let n = buffered.len().min(self.width - self.row.len());
+            // capacity checked by `Frame::rows`
+            self.row.extend_from_slice(&buffered[..n]).ok();
+            self.cursor += n;
+        }
+
+        let row = if self.is_interlaced {
+            interlaced_row(self.n, self.height)
+        } else {
+            self.n
+        };
+        self.n += 1;
+        let indices = core::mem::take(&mut self.row);
+        Some(Ok((self.top.saturating_add(row as u16), indices)))
+    }
+}
+
+/// Iterator over the decoded pixels of a frame, see `Frame::pixels`.
+pub struct PixelIterator<'a, C> {
+    indices: IndexPixelIterator<'a>,
+    global_color_table: Option<ColorTable<'a>>,
+    transparent_color_index: Option<u8>,
+    _marker: PhantomData<C>,
+}
+
+impl<'a, C> Iterator for PixelIterator<'a, C>
+where
+    C: PixelColor + From<Rgb888>,
+{
+    type Item = Pixel<C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (x, y, color_index) = self.indices.next()?;
+            if self.transparent_color_index == Some(color_index) {
+                continue;
+            }
+            let Some(color_table) = self.indices.local_color_table().or(self.global_color_table)
+            else {
+                continue;
+            };
+            // out of range index in a malformed frame, treat as transparent
+            let Some(color) = color_table.get(color_index) else {
+                continue;
+            };
+            return Some(Pixel(Point::new(i32::from(x), i32::from(y)), color.into()));
+        }
+    }
+}
+
+/// Decoding state of the current image block in an `IndexPixelIterator`.
+struct IndexDecodeState<'a> {
+    pixel: BlockCursor,
+    local_color_table: Option<ColorTable<'a>>,
+    decoder: lzw::Decoder<LenPrefixRawDataView<'a>>,
+    // position in the decoder buffer
+    cursor: usize,
+}
+
+/// Iterator over the color indices of a frame, see `Frame::index_pixels`.
+pub struct IndexPixelIterator<'a> {
+    remain_raw_data: &'a [u8],
+    screen_width: u16,
+    screen_height: u16,
+    image: Option<IndexDecodeState<'a>>,
+    // error that stopped the iteration
+    error: Option<ParseError>,
+}
+
+impl<'a> IndexPixelIterator<'a> {
+    // Local color table of the image block of the last yielded pixel.
+    fn local_color_table(&self) -> Option<ColorTable<'a>> {
+        self.image.as_ref()?.local_color_table
+    }
+}
+
+impl Iterator for IndexPixelIterator<'_> {
+    type Item = (u16, u16, u8);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(image) = &mut self.image {
+                match image.decoder.buffer().get(image.cursor) {
+                    // codes past the last pixel are ignored
+                    Some(_) if image.pixel.remaining() == 0 => (),
+                    Some(&color_index) => {
+                        image.cursor += 1;
+                        let (x, y) = image.pixel.advance();
+                        if x >= u32::from(self.screen_width) || y >= u32::from(self.screen_height) {
+                            // image block exceeds the logical screen
+                            continue;
+                        }
+                        return Some((x as u16, y as u16, color_index));
+                    }
+                    None => match image.decoder.decode_next() {
+                        Ok(Some(_)) => {
+                            image.cursor = 0;
+                            continue;
+                        }
+                        Ok(None) => (),
+                        Err(e) => self.error = Some(e),
+                    },
+                }
+                self.image = None;
+            }
+
+            let (input, seg) = match Segment::parse(self.remain_raw_data) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    self.error.get_or_insert(e);
+                    self.remain_raw_data = &[];
+                    return None;
+                }
+            };
+            self.remain_raw_data = input;
+            match seg {
+                Segment::Extension(ExtensionBlock::GraphicControl(_)) | Segment::Trailer => {
+                    // overflows to the next frame
+                    self.remain_raw_data = &[];
+                    return None;
+                }
+                Segment::Image(image_block) => {
+                    let raw_image_data = LenPrefixRawDataView::new(image_block.image_data);
+                    self.image = Some(IndexDecodeState {
+                        pixel: BlockCursor::new(&image_block),
+                        local_color_table: image_block.local_color_table,
+                        decoder: lzw::Decoder::new(raw_image_data, image_block.lzw_min_code_size),
+                        cursor: 0,
+                    });
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+impl<'a, C> OriginDimensions for Frame<'a, C> {
+    fn size(&self) -> Size {
+        Size::new(self.header.width as _, self.header.height as _)
+    }
+}
+
+/// Error returned by `Frame::try_draw`.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum DrawError<E> {
+    /// The frame data is malformed or truncated.
+    Parse(ParseError),
+    /// The draw target returned an error.
+    Draw(E),
+}
+
+// Keeps the errors of the draw target. Malformed data is drawn as much as possible by the
+// draw variants, parse errors are dropped.
+fn ignore_parse_errors<E>(result: Result<(), DrawError<E>>) -> Result<(), E> {
+    match result {
+        Err(DrawError::Draw(e)) => Err(e),
+        _ => Ok(()),
+    }
+}
+
+impl<'a, C> Frame<'a, C>
+where
+    C: PixelColor + From<Rgb888>,
+{
+    /// Draws the frame, reporting malformed or truncated image data.
+    ///
+    /// Unlike `draw`, which silently stops at the first decode error, this returns
+    /// `DrawError::Parse` after drawing the part of the frame that could be decoded.
+    /// Pixels with an out of range color index are skipped and reported as
+    /// `ParseError::InvalidColorIndex` once the frame is drawn.
+    pub fn try_draw<D>(&self, target: &mut D) -> Result<(), DrawError<D::Error>>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        self.draw_area(target, None, None)
+    }
+
+    /// Draws the frame into a row-major buffer of `stride` pixels per row.
+    ///
+    /// Pixel `(x, y)` of the logical screen is written at `buf[y * stride + x]`, pixels
+    /// outside of the buffer are skipped. Transparent pixels are skipped too, leaving the
+    /// previous content of the buffer in place.
+    pub fn draw_into(&self, buf: &mut [C], stride: usize) -> Result<(), ParseError> {
+        let height = buf.len().checked_div(stride).unwrap_or(0);
+        let mut canvas = Canvas {
+            pixels: buf,
+            size: Size::new(stride as u32, height as u32),
+        };
+        match self.try_draw(&mut canvas) {
+            Ok(()) => Ok(()),
+            Err(DrawError::Parse(e)) => Err(e),
+            Err(DrawError::Draw(e)) => match e {},
+        }
+    }
+
+    /// Draws the frame downscaled by `numerator / denominator`, using nearest-neighbor sampling.
+    ///
+    /// Only downscaling is supported, `numerator` must not be greater than `denominator`.
+    /// The whole frame is still decoded, but only the sampled pixels are drawn, at scaled
+    /// coordinates of the logical screen.
+    pub fn draw_scaled<D>(
+        &self,
+        target: &mut D,
+        numerator: u32,
+        denominator: u32,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let mut target = Scaled::new(target, numerator, denominator);
+        ignore_parse_errors(self.draw_area(&mut target, None, None))
+    }
+
+    /// Draws the frame stretched to correct the pixel aspect ratio of the GIF.
+    ///
+    /// Wide pixels are stretched horizontally, tall pixels vertically, by duplicating
+    /// columns or rows. Without aspect ratio information, the frame is drawn 1:1 as `draw`.
+    pub fn draw_aspect_correct<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let ratio = u32::from(self.header.pixel_aspect_ratio);
+        let result = match ratio + 15 {
+            15 | 64 => self.draw_area(target, None, None),
+            width if width > 64 => {
+                self.draw_area(&mut Stretched::new(target, (width, 64), (1, 1)), None, None)
+            }
+            width => self.draw_area(&mut Stretched::new(target, (1, 1), (64, width)), None, None),
+        };
+        ignore_parse_errors(result)
+    }
+
+    /// Draws the frame rotated clockwise by `rotation`, for displays mounted rotated.
+    ///
+    /// Pixels are rotated within the logical screen as they are decoded, the logical
+    /// screen is drawn at the origin of the target.
+    pub fn draw_rotated<D>(&self, target: &mut D, rotation: Rotation) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let mut target = Rotated::new(target, rotation, self.size());
+        ignore_parse_errors(self.draw_area(&mut target, None, None))
+    }
+
+    /// Draws the part of the frame inside `area`, relative to the top left corner of the
+    /// image block instead of the logical screen.
+    ///
+    /// Pixels are drawn at their logical screen position, as `draw_sub_image` does.
+    pub fn draw_sub_image_local<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let area = Rectangle::new(area.top_left + self.bounding_box().top_left, area.size);
+        self.draw_sub_image(target, &area)
+    }
+
+    /// Draws the frame with the top left corner of its image block at the origin of the
+    /// target, ignoring its `left` and `top` offset, as for sprites cut from a strip.
+    ///
+    /// Other image blocks of the frame are drawn relative to the first one.
+    pub fn draw_at_origin<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let offset = self.bounding_box().top_left;
+        ignore_parse_errors(self.draw_area(&mut target.translated(-offset), None, None))
+    }
+
+    /// Draws the part of the frame inside `area` as `draw_sub_image`, and returns the area
+    /// actually written, for minimal flushes of partial refresh displays.
+    ///
+    /// The returned area spans the rows and the columns from the first to the last one with a
+    /// drawn pixel, in all the image blocks of the frame, fully transparent rows and columns
+    /// at the edges are left out. It is zero sized if no pixel is drawn.
+    pub fn draw_sub_image_tracked<D>(
+        &self,
+        target: &mut D,
+        area: &Rectangle,
+    ) -> Result<Rectangle, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let mut target = RowTracker::new(target);
+        ignore_parse_errors(self.draw_area(&mut target, Some(area), None))?;
+        let (Some((top, bottom)), Some((left, right))) = (target.rows, target.cols) else {
+            return Ok(Rectangle::zero());
+        };
+        Ok(Rectangle::with_corners(
+            Point::new(left, top),
+            Point::new(right, bottom),
+        ))
+    }
+
+    /// Draws the frame as `draw`, awaiting `yield_fn()` every `chunks_per_yield` decoded
+    /// chunks of color indices, to let the executor run other tasks while decoding.
+    ///
+    /// The decoder is held by the returned future across the await points.
+    #[cfg(feature = "async")]
+    pub async fn draw_async<D, Y, F>(
+        &self,
+        target: &mut D,
+        chunks_per_yield: usize,
+        mut yield_fn: Y,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+        Y: FnMut() -> F,
+        F: core::future::Future<Output = ()>,
+    {
+        let screen_size = (u32::from(self.header.width), u32::from(self.header.height));
+        let mut chunks = 0;
+        let mut input = self.raw_data;
+        // malformed data, draw as much as possible
+        while let Ok((input0, seg)) = Segment::parse(input) {
+            input = input0;
+            let image_block = match seg {
+                Segment::Extension(ExtensionBlock::GraphicControl(_)) | Segment::Trailer => break,
+                Segment::Image(image_block) => image_block,
+                _ => continue,
+            };
+            let Some(color_table) = image_block.local_color_table.or(self.global_color_table)
+            else {
+                break;
+            };
+            let raw_image_data = LenPrefixRawDataView::new(image_block.image_data);
+            let mut decoder = lzw::Decoder::new(raw_image_data, image_block.lzw_min_code_size);
+            let mut block = BlockPixels::new(
+                &image_block,
+                screen_size,
+                self.transparent_color_index,
+                |color_index| color_table.get(color_index).map(C::from),
+            );
+
+            while let Ok(Some(decoded)) = decoder.decode_next() {
+                if block.draw_chunk(target, decoded, self.is_transparent)? {
+                    break;
+                }
+                chunks += 1;
+                if chunks % chunks_per_yield.max(1) == 0 {
+                    yield_fn().await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the frame with `options`, combining the variants of `draw`.
+    ///
+    /// Panics if the scale of `options` is not a downscale, as `draw_scaled`.
+    pub fn draw_with<D>(&self, target: &mut D, options: &DrawOptions<C>) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let (numerator, denominator) = options.scale;
+        let result = if numerator == denominator {
+            self.draw_with_unscaled(target, options)
+        } else {
+            let mut target = Scaled::new(target, numerator.into(), denominator.into());
+            self.draw_with_unscaled(&mut target, options)
+        };
+        ignore_parse_errors(result)
+    }
+
+    fn draw_with_unscaled<D>(
+        &self,
+        target: &mut D,
+        options: &DrawOptions<C>,
+    ) -> Result<(), DrawError<D::Error>>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let frame = Frame {
+            is_transparent: self.is_transparent && options.respect_transparency,
+            ..*self
+        };
+        let area = options.clip.as_ref();
+        if !options.use_fast_path {
+            return frame.draw_area(target, area, options.background);
+        }
+        frame.draw_mapped(target, area, options.background, |color_table| {
+            let color_table = color_table?;
+            let lut: heapless::Vec<C, 256> = (0..color_table.len().min(256))
+                .filter_map(|index| color_table.get(index as u8))
+                .map(C::from)
+                .collect();
+            Some(move |color_index| lut.get(color_index as usize).copied())
+        })
+    }
+
+    /// Draws the frame, replacing transparent pixels with `background`.
+    ///
+    /// Use this when the target is not cleared between frames, as `draw` leaves the
+    /// previous content at transparent pixels.
+    pub fn draw_with_background<D>(&self, target: &mut D, background: C) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        ignore_parse_errors(self.draw_area(target, None, Some(background)))
+    }
+
+    /// Draws the frame, replacing transparent pixels with the pixels of `prev`.
+    ///
+    /// This renders frames following one disposed with `DisposalMethod::RestoreToPrevious`
+    /// by keeping the decoded frame before it, which costs one `DecodedFrame` buffer instead
+    /// of a snapshot of the logical screen. Transparent pixels outside of `prev`, or
+    /// transparent in `prev` too, are skipped.
+    pub fn draw_over<D, const N: usize>(
+        &self,
+        target: &mut D,
+        prev: &DecodedFrame<'_, C, N>,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let transparent_color_index = if self.is_transparent {
+            Some(self.transparent_color_index)
+        } else {
+            None
+        };
+        let global_color_table = self.global_color_table;
+        let mut pixels = self.index_pixels();
+        // malformed data, draw as much as possible
+        target.draw_iter(core::iter::from_fn(|| loop {
+            let (x, y, color_index) = pixels.next()?;
+            let pt = Point::new(i32::from(x), i32::from(y));
+            let color = if transparent_color_index == Some(color_index) {
+                prev.color_at(pt)
+            } else {
+                let Some(color_table) = pixels.local_color_table().or(global_color_table) else {
+                    continue;
+                };
+                color_table.get(color_index).map(C::from)
+            };
+            if let Some(color) = color {
+                return Some(Pixel(pt, color));
+            }
+        }))
+    }
+
+    /// Draws the frame on top of the previous one, returning the area to refresh.
+    ///
+    /// If the disposal method of the previous frame keeps it in place, see
+    /// `DisposalMethod::keeps_frame`, it stays on the target and only the image block of
+    /// this frame changes, which is drawn and returned as the dirty area. Other disposal
+    /// methods are not handled: nothing is drawn and `None` is returned, such frames can
+    /// be drawn with a `Compositor`.
+    pub fn draw_dirty<D>(
+        &self,
+        prev_disposal: DisposalMethod,
+        target: &mut D,
+    ) -> Result<Option<Rectangle>, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        if !prev_disposal.keeps_frame() {
+            return Ok(None);
+        }
+        ImageDrawable::draw(self, target)?;
+        let screen = Rectangle::new(Point::zero(), self.size());
+        Ok(Some(self.bounding_box().intersection(&screen)))
+    }
+
+    /// Draws the frame, calling `progress(pixels_done, pixels_total)` during decoding.
+    ///
+    /// `pixels_total` is the number of pixels of all image blocks of the frame, transparent
+    /// ones included. The callback is called when decoding reaches a new row, not for each
+    /// row of narrow images, and once all pixels are decoded. It can update a progress bar,
+    /// or yield to other tasks.
+    pub fn draw_with_progress<D, P>(&self, target: &mut D, mut progress: P) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+        P: FnMut(u32, u32),
+    {
+        let pixels_total = self.pixels_total();
+        let result = self.draw_mapped_with_progress(
+            target,
+            None,
+            None,
+            |color_table| {
+                let color_table = color_table?;
+                Some(move |color_index| color_table.get(color_index).map(C::from))
+            },
+            |pixels_done| progress(pixels_done, pixels_total),
+        );
+        ignore_parse_errors(result)
+    }
+
+    // Number of pixels of all image blocks of the frame.
+    fn pixels_total(&self) -> u32 {
+        let mut total: u32 = 0;
+        let mut input = self.raw_data;
+        while let Ok((input0, seg)) = Segment::parse(input) {
+            input = input0;
+            match seg {
+                Segment::Extension(ExtensionBlock::GraphicControl(_)) | Segment::Trailer => break,
+                Segment::Image(ImageBlock { width, height, .. }) => {
+                    total = total.saturating_add(u32::from(width) * u32::from(height));
+                }
+                _ => (),
+            }
+        }
+        total
+    }
+
+    fn draw_area<D>(
+        &self,
+        target: &mut D,
+        area: Option<&Rectangle>,
+        background: Option<C>,
+    ) -> Result<(), DrawError<D::Error>>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        self.draw_mapped(target, area, background, |color_table| {
+            let color_table = color_table?;
+            Some(move |color_index| color_table.get(color_index).map(C::from))
+        })
+    }
+}
+
+impl<'a, C> Frame<'a, C> {
+    /// Draws the frame on a monochrome target.
+    ///
+    /// Colors with a luminance greater than or equal to `threshold` are drawn as
+    /// `BinaryColor::On`, others as `BinaryColor::Off`. Transparent pixels are skipped.
+    pub fn draw_mono<D>(&self, target: &mut D, threshold: u8) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let result = self.draw_mapped(target, None, None, |color_table| {
+            let color_table = color_table?;
+            // luminance is computed once per color table entry
+            let mut is_on = [0u32; 8];
+            for index in 0..color_table.len().min(256) {
+                let color = color_table.get(index as u8).unwrap_or_default();
+                let luma = (77 * u32::from(color.r())
+                    + 150 * u32::from(color.g())
+                    + 29 * u32::from(color.b()))
+                    >> 8;
+                if luma >= u32::from(threshold) {
+                    is_on[index / 32] |= 1 << (index % 32);
+                }
+            }
+            let len = color_table.len();
+            Some(move |color_index| {
+                let index = color_index as usize;
+                (index < len)
+                    .then(|| BinaryColor::from(is_on[index / 32] & (1 << (index % 32)) != 0))
+            })
+        });
+        ignore_parse_errors(result)
+    }
+
+    /// Draws the frame on a `Rgb565` target.
+    ///
+    /// Each color table is converted once with `ColorTable::to_rgb565_lut`, instead of
+    /// converting each pixel from `Rgb888` as `draw` does.
+    pub fn draw_rgb565<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Rgb565>,
+    {
+        let result = self.draw_mapped(target, None, None, |color_table| {
+            let lut = color_table?.to_rgb565_lut();
+            Some(move |color_index| lut.get(color_index as usize).copied())
+        });
+        ignore_parse_errors(result)
+    }
+
+    /// Draws the frame, using `palette[index]` instead of the embedded color tables.
+    ///
+    /// Indices out of the range of `palette` are skipped, as are transparent pixels.
+    pub fn draw_with_palette<D>(&self, target: &mut D, palette: &[C]) -> Result<(), D::Error>
+    where
+        C: PixelColor,
+        D: DrawTarget<Color = C>,
+    {
+        let result = self.draw_mapped(target, None, None, |_| {
+            Some(|color_index| palette.get(color_index as usize).copied())
+        });
+        ignore_parse_errors(result)
+    }
+
+    // Draws the frame, mapping color indices to target colors with the function
+    // returned by `color_map` for the color table of each image block.
+    // `color_map` returns `None` if it needs a color table and there is none.
+    fn draw_mapped<D, F, M>(
+        &self,
+        target: &mut D,
+        area: Option<&Rectangle>,
+        background: Option<D::Color>,
+        color_map: F,
+    ) -> Result<(), DrawError<D::Error>>
+    where
+        D: DrawTarget,
+        F: FnMut(Option<ColorTable<'a>>) -> Option<M>,
+        M: Fn(u8) -> Option<D::Color>,
+    {
+        self.draw_mapped_with_progress(target, area, background, color_map, |_| ())
+    }
+
+    // Same as `draw_mapped`, calling `progress` with the number of decoded pixels of the
+    // frame each time decoding reaches a new row.
+    fn draw_mapped_with_progress<D, F, M, P>(
+        &self,
+        target: &mut D,
+        area: Option<&Rectangle>,
+        background: Option<D::Color>,
+        mut color_map: F,
+        mut progress: P,
+    ) -> Result<(), DrawError<D::Error>>
+    where
+        D: DrawTarget,
+        F: FnMut(Option<ColorTable<'a>>) -> Option<M>,
+        M: Fn(u8) -> Option<D::Color>,
+        P: FnMut(u32),
+    {
+        // pixels of the previous image blocks
+        let mut pixels_done: u32 = 0;
+        let screen_width = u32::from(self.header.width);
+        let screen_height = u32::from(self.header.height);
+        let mut invalid_color_index = false;
+        // the decoder is reused for all image blocks of the frame
+        let mut decoder: Option<lzw::Decoder<LenPrefixRawDataView<'a>>> = None;
+        let mut input = self.raw_data;
+        loop {
+            let (input0, seg) = Segment::parse(input).map_err(DrawError::Parse)?;
+            input = input0;
+            match seg {
+                Segment::Extension(ExtensionBlock::GraphicControl(_)) | Segment::Trailer => {
+                    // overflows to the next frame
+                    break;
+                }
+                Segment::Image(
+                    image_block @ ImageBlock {
+                        top,
+                        width,
+                        height,
+                        is_interlaced,
+                        lzw_min_code_size,
+                        local_color_table,
+                        image_data,
+                        ..
+                    },
+                ) => {
+                    // bottom row of the clip area, rows are decoded in order unless interlaced
+                    let clip_bottom = match area {
+                        Some(area) if !is_interlaced => {
+                            Some(area.bottom_right().map_or(i32::MIN, |pt| pt.y))
+                        }
+                        _ => None,
+                    };
+                    let Some(color_map) = color_map(local_color_table.or(self.global_color_table))
+                    else {
+                        return Err(DrawError::Parse(ParseError::MissingColorTable));
+                    };
+                    let raw_image_data = LenPrefixRawDataView::new(image_data);
+                    let decoder = match decoder.as_mut() {
+                        Some(decoder) => {
+                            decoder.reset(raw_image_data, lzw_min_code_size);
+                            decoder
+                        }
+                        None => {
+                            decoder.insert(lzw::Decoder::new(raw_image_data, lzw_min_code_size))
+                        }
+                    };
+
+                    let n_pixels = u32::from(width) * u32::from(height);
+                    let mut block = BlockPixels {
+                        area: area.copied(),
+                        background,
+                        ..BlockPixels::new(
+                            &image_block,
+                            (screen_width, screen_height),
+                            self.transparent_color_index,
+                            color_map,
+                        )
+                    };
+                    let mut reported_row = 0;
+
+                    while let Some(decoded) = decoder.decode_next().map_err(DrawError::Parse)? {
+                        let done = block
+                            .draw_chunk(target, decoded, self.is_transparent)
+                            .map_err(DrawError::Draw)?;
+                        if block.cursor.row > reported_row || done {
+                            reported_row = block.cursor.row;
+                            progress(pixels_done + block.cursor.idx);
+                        }
+                        if done {
+                            break;
+                        }
+                        let row = i32::from(top) + block.cursor.row as i32;
+                        if clip_bottom.is_some_and(|bottom| row > bottom) {
+                            // the rest of the image is below the clip area
+                            break;
+                        }
+                    }
+
+                    invalid_color_index |= block.invalid_color_index;
+                    if clip_bottom.is_none() && block.cursor.idx < n_pixels {
+                        // image data ended before all pixels were decoded
+                        return Err(DrawError::Parse(ParseError::UnexpectedEndOfFile));
+                    }
+                    pixels_done += n_pixels;
+                }
+                _ => (),
+            }
+        }
+
+        if invalid_color_index {
+            return Err(DrawError::Parse(ParseError::InvalidColorIndex));
+        }
+        Ok(())
+    }
+}
+
+// Position of the next pixel of an image block in decoding order, carried forward without
+// a division per pixel, which is slow on cores without a hardware divider.
+#[derive(Debug, Clone, Copy)]
+struct BlockCursor {
+    left: u32,
+    top: u32,
+    width: u32,
+    height: u32,
+    is_interlaced: bool,
+    col: u32,
+    row: u32,
+    // index of the next pixel in the image block
+    idx: u32,
+    // logical screen row of `row`, mapped once per row for interlaced images
+    y: u32,
+}
+
+impl BlockCursor {
+    fn new(image_block: &ImageBlock<'_>) -> Self {
+        BlockCursor {
+            left: u32::from(image_block.left),
+            top: u32::from(image_block.top),
+            width: u32::from(image_block.width),
+            height: u32::from(image_block.height),
+            is_interlaced: image_block.is_interlaced,
+            col: 0,
+            row: 0,
+            idx: 0,
+            y: u32::from(image_block.top),
+        }
+    }
+
+    // Number of pixels of the image block not walked yet.
+    #[inline(always)]
+    fn remaining(&self) -> usize {
+        (self.width * self.height - self.idx) as usize
+    }
+
+    // Returns the logical screen position of the next pixel, and moves past it.
+    #[inline(always)]
+    fn advance(&mut self) -> (u32, u32) {
+        let pos = (self.left + self.col, self.y);
+        self.idx += 1;
+        self.col += 1;
+        if self.col == self.width {
+            self.col = 0;
+            self.row += 1;
+            self.y = self.top
+                + if self.is_interlaced {
+                    interlaced_row(self.row as usize, self.height as usize) as u32
+                } else {
+                    self.row
+                };
+        }
+        pos
+    }
+}
+
+// Pixels of an image block drawn by `Frame::draw_mapped`, chunk by chunk as the color
+// indices are decoded.
+struct BlockPixels<C, M> {
+    cursor: BlockCursor,
+    screen_width: u32,
+    screen_height: u32,
+    area: Option<Rectangle>,
+    background: Option<C>,
+    transparent_color_index: u8,
+    color_map: M,
+    invalid_color_index: bool,
+}
+
+impl<C: PixelColor, M: Fn(u8) -> Option<C>> BlockPixels<C, M> {
+    // Starts at the first pixel of `image_block`, without clip area nor background.
+    fn new(
+        image_block: &ImageBlock<'_>,
+        (screen_width, screen_height): (u32, u32),
+        transparent_color_index: u8,
+        color_map: M,
+    ) -> Self {
+        BlockPixels {
+            cursor: BlockCursor::new(image_block),
+            screen_width,
+            screen_height,
+            area: None,
+            background: None,
+            transparent_color_index,
+            color_map,
+            invalid_color_index: false,
+        }
+    }
+
+    // Maps the next decoded color indices to pixels. The transparent color index is only
+    // checked if `TRANSPARENT` is true.
+    fn pixels<'s, const TRANSPARENT: bool>(
+        &'s mut self,
+        indices: &'s [u8],
+    ) -> impl Iterator<Item = Pixel<C>> + 's {
+        indices.iter().filter_map(move |&color_index| {
+            let (x, y) = self.cursor.advance();
+            let is_transparent = TRANSPARENT && color_index == self.transparent_color_index;
+            if is_transparent && self.background.is_none() {
+                // skip drawing transparent color
+                return None;
+            }
+            if x >= self.screen_width || y >= self.screen_height {
+                // image block exceeds the logical screen
+                return None;
+            }
+            let pt = Point::new(x as i32, y as i32);
+            if self.area.is_some_and(|area| !area.contains(pt)) {
+                return None;
+            }
+            if is_transparent {
+                return self.background.map(|color| Pixel(pt, color));
+            }
+            // out of range index in a malformed frame, treat as transparent
+            let Some(color) = (self.color_map)(color_index) else {
+                self.invalid_color_index = true;
+                return None;
+            };
+            Some(Pixel(pt, color))
+        })
+    }
+
+    // Draws the next decoded color indices, and returns `true` once all the pixels of the
+    // image block are drawn. Codes past the last pixel are ignored, such as padding bits
+    // decoded as codes when the end code is missing.
+    fn draw_chunk<D>(
+        &mut self,
+        target: &mut D,
+        decoded: &[u8],
+        is_transparent: bool,
+    ) -> Result<bool, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let decoded = &decoded[..decoded.len().min(self.cursor.remaining())];
+        // opaque frames skip the check of the transparent color index
+        if is_transparent {
+            target.draw_iter(self.pixels::<true>(decoded))?;
+        } else {
+            target.draw_iter(self.pixels::<false>(decoded))?;
+        }
+        Ok(self.cursor.remaining() == 0)
+    }
+}
+
+impl<'a, C> ImageDrawable for Frame<'a, C>
+where
+    C: PixelColor + From<Rgb888>,
+{
+    type Color = C;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        ignore_parse_errors(self.draw_area(target, None, None))
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        ignore_parse_errors(self.draw_area(target, Some(area), None))
+    }
+}
+
+#[cfg(feature = "framebuffer")]
+impl<'a, C> Frame<'a, C>
+where
+    C: PixelColor + From<Rgb888>,
+{
+    /// Draws the frame on a new framebuffer, cleared to the zero color.
+    ///
+    /// `W` and `H` are usually the logical screen size, pixels outside of them are discarded.
+    /// `N` must be `embedded_graphics::framebuffer::buffer_size::<C>(W, H)`, which is
+    /// checked at compile time.
+    pub fn to_framebuffer<BO, const W: usize, const H: usize, const N: usize>(
+        &self,
+    ) -> Framebuffer<C, C::Raw, BO, W, H, N>
+    where
+        Framebuffer<C, C::Raw, BO, W, H, N>: DrawTarget<Color = C>,
+    {
+        let mut framebuffer = Framebuffer::new();
+        // malformed data, draw as much as possible
+        self.draw(&mut framebuffer).ok();
+        framebuffer
+    }
+}
+
+/// A frame decoded into a buffer of color indices, for cheap repeated drawing.
+///
+/// `N` is the capacity of the buffer in pixels, it must be large enough for the image
+/// block of the frame. Only the first image block of the frame is decoded.
+pub struct DecodedFrame<'a, C, const N: usize> {
+    pub delay_centis: u16,
+    screen_size: Size,
+    area: Rectangle,
+    color_table: Option<ColorTable<'a>>,
+    transparent_color_index: Option<u8>,
+    indices: heapless::Vec<u8, N>,
+    _marker: PhantomData<C>,
+}
+
+impl<'a, C, const N: usize> DecodedFrame<'a, C, N> {
+    /// Decodes the frame.
+    ///
+    /// Pixels missing from truncated image data are not drawn. For interlaced images the
+    /// missing pixels are spread over the image, `ParseError::UnexpectedEndOfFile` is
+    /// returned instead.
+    pub fn new(frame: &Frame<'a, C>) -> Result<Self, ParseError> {
+        let mut decoded = Self {
+            delay_centis: frame.delay_centis,
+            screen_size: frame.size(),
+            area: Rectangle::zero(),
+            color_table: frame.global_color_table,
+            transparent_color_index: if frame.is_transparent {
+                Some(frame.transparent_color_index)
+            } else {
+                None
+            },
+            indices: heapless::Vec::new(),
+            _marker: PhantomData,
+        };
+        let image_block = match frame.image_block() {
+            Ok(image_block) => image_block,
+            Err(ParseError::MissingImageBlock) => return Ok(decoded),
+            Err(e) => return Err(e),
+        };
+
+        let n_pixels = image_block.width as usize * image_block.height as usize;
+        if n_pixels > N {
+            return Err(ParseError::BufferTooSmall);
+        }
+        decoded.area = frame.bounding_box();
+        decoded.color_table = image_block.local_color_table.or(decoded.color_table);
+
+        let (left, top) = (u32::from(image_block.left), u32::from(image_block.top));
+        let width = image_block.width as usize;
+        // capacity checked above
+        decoded.indices.resize(n_pixels, 0).ok();
+        let raw_image_data = LenPrefixRawDataView::new(image_block.image_data);
+        let mut decoder = lzw::Decoder::new(raw_image_data, image_block.lzw_min_code_size);
+        let mut cursor = BlockCursor::new(&image_block);
+        while let Some(indices) = decoder.decode_next()? {
+            for &color_index in indices.iter().take(cursor.remaining()) {
+                let (x, y) = cursor.advance();
+                decoded.indices[(y - top) as usize * width + (x - left) as usize] = color_index;
+            }
+        }
+        if cursor.remaining() > 0 {
+            if image_block.is_interlaced {
+                // undecoded rows are interleaved with the decoded ones
+                return Err(ParseError::UnexpectedEndOfFile);
+            }
+            // pixels missing from truncated data are not drawn
+            decoded.indices.truncate(n_pixels - cursor.remaining());
+        }
+        Ok(decoded)
+    }
+
+    /// Returns the area of the logical screen covered by the decoded image.
+    pub fn bounding_box(&self) -> Rectangle {
+        self.area
+    }
+
+    fn draw_area<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        C: PixelColor + From<Rgb888>,
+        D: DrawTarget<Color = C>,
+    {
+        // skip the part of the image block exceeding the logical screen
+        let area = area.intersection(&Rectangle::new(Point::zero(), self.screen_size));
+        target.draw_iter(
+            self.area
+                .intersection(&area)
+                .points()
+                .filter_map(|pt| Some(Pixel(pt, self.color_at(pt)?))),
+        )
+    }
+
+    // Color at `pt` on the logical screen, `None` if transparent or outside of the image.
+    fn color_at(&self, pt: Point) -> Option<C>
+    where
+        C: From<Rgb888>,
+    {
+        if !self.area.contains(pt) {
+            return None;
+        }
+        let offset = pt - self.area.top_left;
+        let idx = offset.y as u32 * self.area.size.width + offset.x as u32;
+        let color_index = *self.indices.get(idx as usize)?;
+        if self.transparent_color_index == Some(color_index) {
+            return None;
+        }
+        Some(self.color_table?.get(color_index)?.into())
+    }
+}
+
+impl<C, const N: usize> OriginDimensions for DecodedFrame<'_, C, N> {
+    fn size(&self) -> Size {
+        self.screen_size
+    }
+}
+
+impl<C, const N: usize> ImageDrawable for DecodedFrame<'_, C, N>
+where
+    C: PixelColor + From<Rgb888>,
+{
+    type Color = C;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        self.draw_area(target, &self.area)
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        self.draw_area(target, area)
+    }
+}
+
+impl<C> fmt::Debug for Frame<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Frame")
+            .field("frame_index", &self.frame_index)
+            .field("delay_centis", &self.delay_centis)
+            .field("is_transparent", &self.is_transparent)
+            .field("transparent_color_index", &self.transparent_color_index)
+            .field("disposal_method", &self.disposal_method)
+            .field("user_input", &self.user_input)
+            .field("len(remain_data)", &self.raw_data.len())
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<C> defmt::Format for Frame<'_, C> {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "Frame {{ frame_index: {}, delay_centis: {} remain: {}}}",
+            self.frame_index,
+            self.delay_centis,
+            self.raw_data.len()
+        );
+    }
+}
+
+/// Parse error.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum ParseError {
+    /// Unexpected end of file.
+    UnexpectedEndOfFile,
+
+    /// Invalid file signatures.
+    InvalidFileSignature([u8; 3]),
+
+    /// Invalid image dimensions.
+    InvalidImageDimensions,
+
+    InvalidByte,
+
+    JunkAfterTrailerByte,
+
+    /// Current size bytes should be a constant.
+    InvalidConstSizeBytes,
+
+    /// Extension with an unknown label.
+    UnsupportedExtension(u8),
+
+    /// The LZW dictionary is full, the image data is malformed or needs a larger dictionary.
+    DictionaryOverflow,
+
+    /// A pixel refers to an entry out of the range of the color table.
+    InvalidColorIndex,
+
+    /// Decode buffer is too small for the image.
+    BufferTooSmall,
+
+    /// The frame contains no image block.
+    MissingImageBlock,
+
+    /// The image has neither a local nor a global color table.
+    MissingColorTable,
+
+    /// Error reading from the underlying reader.
+    Io,
+
+    /// The LZW decoder state was saved before the last clear code.
+    StaleDecoderState,
+
+    /// The data exceeds the limits given to `Gif::from_slice_checked`.
+    LimitExceeded,
+
+    /// Color table data is not a whole number of entries, or more than 256 entries.
+    InvalidColorTableLength,
+
+    /// The input ends within a color table, before the number of entries given by the flags.
+    TruncatedColorTable,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedEndOfFile => f.write_str("unexpected end of file"),
+            ParseError::InvalidFileSignature(signature) => {
+                write!(f, "invalid file signature {:02x?}", signature)
+            }
+            ParseError::InvalidImageDimensions => f.write_str("invalid image dimensions"),
+            ParseError::InvalidByte => f.write_str("invalid byte"),
+            ParseError::JunkAfterTrailerByte => f.write_str("junk after the trailer byte"),
+            ParseError::InvalidConstSizeBytes => f.write_str("invalid block size"),
+            ParseError::UnsupportedExtension(label) => {
+                write!(f, "unsupported extension label 0x{:02x}", label)
+            }
+            ParseError::DictionaryOverflow => f.write_str("LZW dictionary overflow"),
+            ParseError::InvalidColorIndex => f.write_str("color index out of the color table"),
+            ParseError::BufferTooSmall => f.write_str("buffer too small for the image"),
+            ParseError::MissingImageBlock => f.write_str("frame without image block"),
+            ParseError::MissingColorTable => f.write_str("image without color table"),
+            ParseError::Io => f.write_str("I/O error"),
+            ParseError::StaleDecoderState => f.write_str("stale LZW decoder state"),
+            ParseError::LimitExceeded => f.write_str("limit exceeded"),
+            ParseError::InvalidColorTableLength => f.write_str("invalid color table length"),
+            ParseError::TruncatedColorTable => f.write_str("truncated color table"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Counts drawn pixels
+    struct PixelCounter(usize);
+
+    impl OriginDimensions for PixelCounter {
+        fn size(&self) -> Size {
+            Size::new(256, 256)
+        }
+    }
+
+    impl DrawTarget for PixelCounter {
+        type Color = Rgb888;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            self.0 += pixels.into_iter().count();
+            Ok(())
+        }
+    }
+
+    // 1x1 GIF87a without Graphic Control Extension
+    const GIF87A_1X1: &[u8] =
+        b"GIF87a\x01\x00\x01\x00\x80\x00\x00\xff\x00\x00\x00\x00\x00\x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x44\x01\x00\x3b";
+
+    #[test]
+    fn test_first_frame_without_graphic_control() {
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        let frame = gif.first_frame().unwrap();
+        assert_eq!(frame.delay_centis, 0);
+        assert_eq!(frame.effective_delay_centis(10), 10);
+        assert!(!frame.is_transparent);
+        assert_eq!(frame.graphic_control(), GraphicControl::default());
+        assert!(frame.is_full_opaque());
+        assert_eq!(frame.is_interlaced(), Ok(false));
+        assert_eq!(frame.lzw_min_code_size(), Ok(2));
+        assert_eq!(frame.color_table(), gif.global_color_table());
+        let mut rows = frame.rows::<1>().unwrap();
+        assert_eq!(
+            rows.next(),
+            Some(Ok((0, heapless::Vec::from_slice(&[0]).unwrap())))
+        );
+        assert_eq!(rows.next(), None);
+
+        let mut pixels = frame.pixels();
+        assert_eq!(
+            pixels.next(),
+            Some(Pixel(Point::new(0, 0), Rgb888::new(0xff, 0, 0)))
+        );
+        // padding bits after the end code are not decoded
+        assert_eq!(pixels.next(), None);
+        assert_eq!(gif.frames().count(), 1);
+    }
+
+    #[test]
+    fn test_draw_into() {
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        let frame = gif.first_frame().unwrap();
+        let mut buf = [Rgb888::BLUE; 4];
+        assert_eq!(frame.draw_into(&mut buf, 2), Ok(()));
+        assert_eq!(buf, [Rgb888::RED, Rgb888::BLUE, Rgb888::BLUE, Rgb888::BLUE]);
+        assert_eq!(frame.draw_into(&mut buf, 0), Ok(()));
+    }
+
+    #[test]
+    fn test_extensions() {
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        assert_eq!(gif.extensions().count(), 0);
+
+        // comment, image, graphic control, image
+        let data = b"GIF89a\x01\x00\x01\x00\x80\x00\x00\xff\x00\x00\x00\x00\x00\
+            \x21\xfe\x02hi\x00\
+            \x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x44\x01\x00\
+            \x21\xf9\x04\x00\x0a\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x44\x01\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let mut extensions = gif.extensions();
+        assert_eq!(
+            extensions.next(),
+            Some(ExtensionBlock::Comment(b"\x02hi\x00"))
+        );
+        assert!(matches!(
+            extensions.next(),
+            Some(ExtensionBlock::GraphicControl(GraphicControl {
+                delay_centis: 10,
+                ..
+            }))
+        ));
+        assert_eq!(extensions.next(), None);
+    }
+
+    #[test]
+    fn test_draw_with() {
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        let frame = gif.first_frame().unwrap();
+        let count = |options: DrawOptions<Rgb888>| {
+            let mut counter = PixelCounter(0);
+            frame.draw_with(&mut counter, &options).unwrap();
+            counter.0
+        };
+        assert_eq!(count(DrawOptions::new()), 1);
+        assert_eq!(count(DrawOptions::new().with_fast_path(true)), 1);
+        assert_eq!(count(DrawOptions::new().with_scale(1, 2)), 1);
+        let clip = Rectangle::new(Point::new(1, 0), Size::new(1, 1));
+        assert_eq!(count(DrawOptions::new().with_clip(clip)), 0);
+
+        let mut buf = [Rgb888::BLUE; 1];
+        frame.draw_into(&mut buf, 1).unwrap();
+        let mut fast = [Rgb888::BLUE; 1];
+        let options = DrawOptions::new().with_fast_path(true);
+        let mut canvas = Canvas {
+            pixels: &mut fast,
+            size: Size::new(1, 1),
+        };
+        frame.draw_with(&mut canvas, &options).unwrap();
+        assert_eq!(buf, fast);
+    }
+
+    #[test]
+    fn test_draw_dirty() {
+        // small image blocks on top of not disposed frames
+        let gif = Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-128x64.gif")).unwrap();
+        let size = gif.dimensions();
+        let mut expected = [Rgb888::WHITE; 128 * 64];
+        let mut compositor = Compositor::new(&mut expected, size, Rgb888::WHITE).unwrap();
+        let mut buf = [Rgb888::WHITE; 128 * 64];
+        let mut canvas = Canvas {
+            pixels: &mut buf,
+            size,
+        };
+
+        let mut prev_disposal = DisposalMethod::DoNotDispose;
+        for frame in gif.frames() {
+            let dirty = frame.draw_dirty(prev_disposal, &mut canvas).unwrap();
+            assert_eq!(dirty, Some(frame.bounding_box()));
+            compositor.draw_frame(&frame).unwrap();
+            assert!(canvas.pixels == compositor.pixels());
+            prev_disposal = frame.disposal_method;
+        }
+
+        let frame = gif.first_frame().unwrap();
+        let dirty = frame.draw_dirty(DisposalMethod::RestoreToBackground, &mut canvas);
+        assert_eq!(dirty, Ok(None));
+    }
+
+    // Checks that the drawn pixels are the ones of `Frame::pixels`
+    struct PixelsChecker<I>(I);
+
+    impl<I> OriginDimensions for PixelsChecker<I> {
+        fn size(&self) -> Size {
+            Size::new(160, 80)
+        }
+    }
+
+    impl<I: Iterator<Item = Pixel<Rgb888>>> DrawTarget for PixelsChecker<I> {
+        type Color = Rgb888;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<P>(&mut self, pixels: P) -> Result<(), Self::Error>
+        where
+            P: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for pixel in pixels {
+                assert_eq!(Some(pixel), self.0.next());
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_draw_coordinates() {
+        // image blocks narrower than the logical screen, not aligned to decoded strings
+        let gif = Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-160x80.gif")).unwrap();
+        for frame in gif.frames() {
+            let mut checker = PixelsChecker(frame.pixels());
+            frame.try_draw(&mut checker).unwrap();
+            assert_eq!(checker.0.next(), None);
+        }
+    }
+
+    #[test]
+    fn test_frames_stepped() {
+        let gif = Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-160x80.gif")).unwrap();
+        let total_delay = |frames: &mut dyn Iterator<Item = Frame<'_, Rgb888>>| {
+            frames
+                .map(|frame| u32::from(frame.delay_centis))
+                .sum::<u32>()
+        };
+        assert_eq!(gif.frame_count(), 117);
+        assert_eq!(gif.frames_stepped(2).count(), 59);
+        assert_eq!(
+            total_delay(&mut gif.frames_stepped(2)),
+            total_delay(&mut gif.frames())
+        );
+        let mut frames = gif.frames_stepped(3);
+        assert_eq!(frames.nth(1).map(|frame| frame.frame_index), Some(3));
+        assert_eq!(gif.frames_stepped(1).count(), 117);
+    }
+
+    #[test]
+    fn test_is_animated() {
+        let gif = Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-160x80.gif")).unwrap();
+        assert!(gif.is_animated());
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        assert!(!gif.is_animated());
+    }
+
+    #[test]
+    fn test_frame_delay() {
+        let gif = Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-160x80.gif")).unwrap();
+        let frame = gif.first_frame().unwrap();
+        assert_eq!(frame.delay_centis, 8);
+        assert_eq!(frame.delay(), Duration::from_millis(80));
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        assert_eq!(gif.first_frame().unwrap().delay(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_frame_iterator_reset() {
+        let gif = Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-160x80.gif")).unwrap();
+        let mut frames = gif.frames();
+        assert_eq!(frames.by_ref().count(), 117);
+        assert!(frames.next().is_none());
+        frames.reset();
+        assert_eq!(frames.next().map(|frame| frame.frame_index), Some(0));
+        assert_eq!(frames.count(), 116);
+    }
+
+    #[test]
+    fn test_frames_limited() {
+        let gif = Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-160x80.gif")).unwrap();
+        assert_eq!(gif.frames_limited(10).count(), 10);
+        assert_eq!(gif.frames_limited(0).count(), 0);
+        assert_eq!(gif.frames_limited(usize::MAX).count(), 117);
+    }
+
+    #[test]
+    fn test_frames_len() {
+        let gif = Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-160x80.gif")).unwrap();
+        let mut frames = gif.frames();
+        assert_eq!(frames.len(), 117);
+        frames.nth(9);
+        assert_eq!(frames.len(), 107);
+        frames.reset();
+        assert_eq!(frames.len(), 117);
+        // counted from the first frame on the first call
+        let mut frames = gif.frames();
+        frames.nth(4);
+        assert_eq!(frames.len(), 112);
+        assert_eq!(gif.frames_limited(10).len(), 10);
+        assert_eq!(gif.frames_limited(200).len(), 117);
+    }
+
+    #[test]
+    fn test_draw_with_progress() {
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        let frame = gif.first_frame().unwrap();
+        let mut calls = heapless::Vec::<_, 4>::new();
+        let mut counter = PixelCounter(0);
+        frame
+            .draw_with_progress(&mut counter, |done, total| {
+                calls.push((done, total)).unwrap();
+            })
+            .unwrap();
+        assert_eq!(calls, [(1, 1)]);
+
+        let gif =
+            Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-240x240.gif")).unwrap();
+        let frame = gif.first_frame().unwrap();
+        let mut last = 0;
+        let mut n = 0;
+        frame
+            .draw_with_progress(&mut counter, |done, total| {
+                assert!(done > last && done <= total);
+                assert_eq!(total, 240 * 240);
+                last = done;
+                n += 1;
+            })
+            .unwrap();
+        assert_eq!(last, 240 * 240);
+        assert!(n > 1);
+    }
+
+    #[test]
+    fn test_draw_aspect_correct() {
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        assert_eq!(gif.raw_gif.header.pixel_aspect_ratio(), 0);
+        let mut counter = PixelCounter(0);
+        let frame = gif.first_frame().unwrap();
+        frame.draw_aspect_correct(&mut counter).unwrap();
+        assert_eq!(counter.0, 1);
+
+        // pixels twice as wide, then twice as tall
+        for ratio in [113, 17] {
+            let mut data = [0; GIF87A_1X1.len()];
+            data.copy_from_slice(GIF87A_1X1);
+            data[12] = ratio;
+            let gif = Gif::<Rgb888>::from_slice(&data).unwrap();
+            let frame = gif.first_frame().unwrap();
+            let mut buf = [Rgb888::BLUE; 4];
+            let mut canvas = Canvas {
+                pixels: &mut buf,
+                size: Size::new(2, 2),
+            };
+            frame.draw_aspect_correct(&mut canvas).unwrap();
+            let expected = if ratio == 113 {
+                [Rgb888::RED, Rgb888::RED, Rgb888::BLUE, Rgb888::BLUE]
+            } else {
+                [Rgb888::RED, Rgb888::BLUE, Rgb888::RED, Rgb888::BLUE]
+            };
+            assert_eq!(buf, expected);
+        }
+    }
+
+    #[test]
+    fn test_index_pixels() {
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        let frame = gif.first_frame().unwrap();
+        let pixels: heapless::Vec<_, 2> = frame.index_pixels().collect();
+        assert_eq!(pixels, [(0, 0, 0)]);
+
+        // interlaced 1x4 image with indices 0, 1, 0, 1 in decoding order
+        let data = b"GIF87a\x01\x00\x04\x00\x80\x00\x00\xff\x00\x00\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x01\x00\x04\x00\x40\x02\x03\x44\x10\x05\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+        let pixels: heapless::Vec<_, 4> = frame.index_pixels().collect();
+        assert_eq!(pixels, [(0, 0, 0), (0, 2, 1), (0, 1, 0), (0, 3, 1)]);
+        let mut out = [2; 4];
+        frame.decode_indices(&mut out).unwrap();
+        assert_eq!(out, [0, 0, 1, 1]);
+
+        let mut out = [1; 12];
+        assert_eq!(
+            frame.decode_rgb888(&mut out[..11]),
+            Err(ParseError::BufferTooSmall)
+        );
+        frame.decode_rgb888(&mut out).unwrap();
+        assert_eq!(out, [255, 0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_loop_count() {
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        assert_eq!(gif.version(), Version::V87a);
+        assert_eq!(gif.loop_count(), None);
+        assert_eq!(gif.frames().count(), 1);
+
+        let data = b"GIF89a\x01\x00\x01\x00\x80\x00\x00\xff\x00\x00\x00\x00\x00\x21\xff\x0bNETSCAPE2.0\x03\x01\x05\x00\x00\x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x44\x01\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        assert_eq!(gif.loop_count(), Some(5));
+        assert_eq!(gif.frames().count(), 1);
+    }
+
+    #[test]
+    fn test_from_slice_checked() {
+        let limits = Limits {
+            max_segments: 1,
+            max_pixels: 1,
+        };
+        assert!(Gif::<Rgb888>::from_slice_checked(GIF87A_1X1, limits).is_ok());
+        for limits in [
+            Limits {
+                max_segments: 0,
+                ..limits
+            },
+            Limits {
+                max_pixels: 0,
+                ..limits
+            },
+        ] {
+            assert_eq!(
+                Gif::<Rgb888>::from_slice_checked(GIF87A_1X1, limits).err(),
+                Some(ParseError::LimitExceeded)
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_gif() {
+        assert!(is_gif(GIF87A_1X1));
+        assert!(is_gif(b"GIF89a"));
+        assert!(!is_gif(b"GIF8"));
+        assert!(!is_gif(b"GIF88a"));
+        assert!(!is_gif(b"\x89PNG\r\n\x1a\n"));
+    }
+
+    #[test]
+    fn test_probe() {
+        let header = probe(GIF87A_1X1).unwrap();
+        assert_eq!(header.version(), Version::V87a);
+        assert_eq!((header.width, header.height), (1, 1));
+        assert!(header.has_global_color_table());
+        assert_eq!(header.global_color_count(), 2);
+        assert_eq!(probe(b"GIF"), Err(ParseError::UnexpectedEndOfFile));
+        // global color table cut short
+        assert_eq!(
+            probe(&GIF87A_1X1[..15]),
+            Err(ParseError::TruncatedColorTable)
+        );
+        assert_eq!(
+            probe(b"GIF87a\x00\x00\x01\x00\x00\x00\x00"),
+            Err(ParseError::InvalidImageDimensions)
+        );
+    }
+
+    #[test]
+    fn test_parse_graphic_control_extension() {
+        let input = b"\xf9\x04\x0b\x0a\x00\x03\x00\x3b";
+        let (input, ext) = ExtensionBlock::parse(input).unwrap();
+        assert_eq!(input, b"\x3b");
+        assert_eq!(
+            ext,
+            ExtensionBlock::GraphicControl(GraphicControl {
+                is_transparent: true,
+                transparent_color_index: 3,
+                delay_centis: 10,
+                disposal_method: DisposalMethod::RestoreToBackground,
+                user_input: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_disposal_method() {
+        for (value, disposal_method) in [
+            (0, DisposalMethod::Unspecified),
+            (1, DisposalMethod::DoNotDispose),
+            (2, DisposalMethod::RestoreToBackground),
+            (3, DisposalMethod::RestoreToPrevious),
+            (4, DisposalMethod::Reserved(4)),
+            (7, DisposalMethod::Reserved(7)),
+        ] {
+            let input = [0xf9, 0x04, value << 2, 0x0a, 0x00, 0x00, 0x00];
+            let Ok((_, ExtensionBlock::GraphicControl(ctrl))) = ExtensionBlock::parse(&input)
+            else {
+                panic!("not a Graphic Control Extension");
+            };
+            assert_eq!(ctrl.disposal_method, disposal_method);
+            assert_eq!(disposal_method.keeps_frame(), !matches!(value, 2 | 3));
+        }
+        // frames without Graphic Control Extension
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        assert_eq!(
+            gif.first_frame().unwrap().disposal_method,
+            DisposalMethod::Unspecified
+        );
+    }
+
+    #[test]
+    fn test_parse_plain_text_extension() {
+        let input = b"\x01\x0c\x01\x00\x02\x00\x10\x00\x08\x00\x08\x08\x01\x00\x02hi\x00\x3b";
+        let (input, ext) = ExtensionBlock::parse(input).unwrap();
+        assert_eq!(input, b"\x3b");
+        assert_eq!(
+            ext,
+            ExtensionBlock::PlainText(PlainText {
+                left: 1,
+                top: 2,
+                width: 16,
+                height: 8,
+                cell_width: 8,
+                cell_height: 8,
+                fg_index: 1,
+                bg_index: 0,
+                text: b"\x02hi\x00",
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_application_extension() {
+        let input = b"\xff\x0bXMP DataXMP\x02<x\x00\x3b";
+        let (input, ext) = ExtensionBlock::parse(input).unwrap();
+        assert_eq!(input, b"\x3b");
+        assert_eq!(
+            ext,
+            ExtensionBlock::Application {
+                identifier: *b"XMP Data",
+                auth_code: *b"XMP",
+                data: b"\x02<x\x00",
+            }
+        );
+
+        let input = b"\xff\x0bNETSCAPE2.0\x03\x01\x05\x00\x00";
+        assert_eq!(
+            ExtensionBlock::parse(input),
+            Ok((
+                &[][..],
+                ExtensionBlock::NetscapeApplication { repetitions: 5 }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_from_slice_lenient() {
+        let mut data = [0u8; 64];
+        let len = GIF87A_1X1.len();
+        data[..len].copy_from_slice(GIF87A_1X1);
+        data[len..len + 4].copy_from_slice(b"junk");
+
+        let gif = Gif::<Rgb888>::from_slice(&data[..len + 4]).unwrap();
+        assert!(gif.raw_gif.raw_block_data.ends_with(b"\x3bjunk"));
+        let gif = Gif::<Rgb888>::from_slice_lenient(&data[..len + 4]).unwrap();
+        assert_eq!(gif.raw_gif.raw_block_data, &GIF87A_1X1[13 + 6..]);
+    }
+
+    #[test]
+    fn test_parse_dimensions() {
+        const SIZE: (u16, u16) = parse_dimensions(include_bytes!("../assets/Ferris-160x80.gif"));
+        assert_eq!(SIZE, (160, 80));
+        assert_eq!(parse_dimensions(GIF87A_1X1), (1, 1));
+    }
+
+    #[test]
+    fn test_split() {
+        let mut data = heapless::Vec::<u8, 128>::new();
+        data.extend_from_slice(GIF87A_1X1).unwrap();
+        data.extend_from_slice(GIF87A_1X1).unwrap();
+        data.extend_from_slice(&GIF87A_1X1[..20]).unwrap();
+
+        let mut gifs = split::<Rgb888>(&data);
+        for _ in 0..2 {
+            let gif = gifs.next().unwrap().unwrap();
+            assert_eq!(gif.frame_count(), 1);
+            assert_eq!(gif.validate(), Ok(()));
+        }
+        assert_eq!(
+            gifs.next().map(|gif| gif.err()),
+            Some(Some(ParseError::UnexpectedEndOfFile))
+        );
+        assert!(gifs.next().is_none());
+        assert!(split::<Rgb888>(&[]).next().is_none());
+    }
+
+    #[test]
+    fn test_segment_reader() {
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        let mut reader = gif.segments();
+        assert!(matches!(reader.next_segment(), Some(Ok(Segment::Image(_)))));
+        assert_eq!(reader.next_segment(), Some(Ok(Segment::Trailer)));
+        assert!(reader.remaining().is_empty());
+        assert_eq!(reader.next_segment(), None);
+
+        let mut reader = SegmentReader::new(b"\x2c\x00");
+        assert_eq!(
+            reader.next_segment(),
+            Some(Err(ParseError::UnexpectedEndOfFile))
+        );
+        assert_eq!(reader.next_segment(), None);
+    }
+
+    #[test]
+    fn test_parse_error_display() {
+        use core::fmt::Write;
+
+        let mut message = heapless::String::<64>::new();
+        write!(message, "{}", ParseError::InvalidFileSignature(*b"GIG")).unwrap();
+        assert_eq!(message, "invalid file signature [47, 49, 47]");
+        message.clear();
+        write!(message, "{}", ParseError::UnsupportedExtension(0x01)).unwrap();
+        assert_eq!(message, "unsupported extension label 0x01");
+        message.clear();
+        write!(message, "{}", ParseError::TruncatedColorTable).unwrap();
+        assert_eq!(message, "truncated color table");
+    }
+
+    #[test]
+    fn test_comment_string() {
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        assert_eq!(gif.comment_string(), None);
+
+        // "hé" split over two sub-blocks, then an invalid byte
+        let mut data = heapless::Vec::<u8, 512>::new();
+        data.extend_from_slice(&GIF87A_1X1[..19]).unwrap();
+        data.extend_from_slice(b"\x21\xfe\x02h\xc3\x02\xa9\xff\x00")
+            .unwrap();
+        data.extend_from_slice(&GIF87A_1X1[19..]).unwrap();
+        let gif = Gif::<Rgb888>::from_slice(&data).unwrap();
+        assert_eq!(gif.comment_string().unwrap(), "h\u{e9}\u{fffd}");
+
+        // truncated to 256 bytes, cutting the last "é"
+        let mut data = heapless::Vec::<u8, 512>::new();
+        data.extend_from_slice(&GIF87A_1X1[..19]).unwrap();
+        data.extend_from_slice(b"\x21\xfe\xff").unwrap();
+        data.extend_from_slice(&[b'a'; 255]).unwrap();
+        data.extend_from_slice(b"\x02\xc3\xa9\x00").unwrap();
+        data.extend_from_slice(&GIF87A_1X1[19..]).unwrap();
+        let gif = Gif::<Rgb888>::from_slice(&data).unwrap();
+        assert_eq!(gif.comment_string().unwrap().as_bytes(), &[b'a'; 255]);
+    }
+
+    #[test]
+    fn test_parse_truncated_color_table() {
+        // 4 entries declared, 1 and a half available
+        let data = b"GIF87a\x02\x00\x03\x00\x81\x00\x00\xff\x00\x00\x00\xff";
+        assert_eq!(
+            Header::parse(data).unwrap_err(),
+            ParseError::TruncatedColorTable
+        );
+        // the descriptor itself is cut, not the table
+        assert_eq!(
+            Header::parse(&data[..11]).unwrap_err(),
+            ParseError::UnexpectedEndOfFile
+        );
+
+        let (input, (header, table)) = Header::parse_lenient(data).unwrap();
+        assert!(input.is_empty());
+        assert_eq!((header.width, header.height), (2, 3));
+        let table = table.unwrap();
+        assert_eq!(table.len(), 4);
+        assert_eq!(table.get(0), Some(Rgb888::RED));
+        assert_eq!(table.get(1), Some(Rgb888::BLACK));
+        assert_eq!(table.get(3), Some(Rgb888::BLACK));
+        assert_eq!(table.get(4), None);
+        assert_eq!(table.to_rgb565_lut().len(), 4);
+
+        assert_eq!(
+            Gif::<Rgb888>::from_slice_lenient(data).err(),
+            Some(ParseError::TruncatedColorTable)
+        );
+        let gif = Gif::<Rgb888>::from_slice_truncated_color_table(data).unwrap();
+        assert_eq!(gif.width(), 2);
+        assert_eq!(gif.frames().count(), 0);
+    }
+
+    #[test]
+    fn test_len_prefix_raw_data_view() {
+        let collect = |data| LenPrefixRawDataView::new(data).collect::<heapless::Vec<u8, 4>>();
+        assert_eq!(collect(b""), []);
+        assert_eq!(collect(b"\x00"), []);
+        assert_eq!(collect(b"\x02\x01\x02\x01\x03\x00"), [1, 2, 3]);
+        // truncated sub-blocks
+        assert_eq!(collect(b"\x03\x01"), [1]);
+        assert_eq!(collect(b"\x01\x01\x02"), [1]);
+    }
+
+    #[test]
+    fn test_interlaced_transparent() {
+        // 1x4 interlaced image, decoded rows 0, 2, 1, 3 with indices 1, 3, 2, 3 where 3
+        // is transparent
+        let data = b"GIF89a\x01\x00\x04\x00\x81\x00\x00\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x21\xf9\x04\x01\x00\x00\x03\x00\
+            \x2c\x00\x00\x00\x00\x01\x00\x04\x00\x40\x02\x03\xcc\x34\x05\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+        assert_eq!(frame.is_interlaced(), Ok(true));
+        let expected = [
+            Pixel(Point::new(0, 0), Rgb888::RED),
+            Pixel(Point::new(0, 1), Rgb888::GREEN),
+        ];
+
+        let mut checker = PixelsChecker(expected.into_iter());
+        frame.draw(&mut checker).unwrap();
+        assert_eq!(checker.0.next(), None);
+        assert!(frame.pixels().eq(expected));
+        let mut checker = PixelsChecker(expected.into_iter());
+        DecodedFrame::<_, 4>::new(&frame)
+            .unwrap()
+            .draw(&mut checker)
+            .unwrap();
+        assert_eq!(checker.0.next(), None);
+
+        let mut checker = PixelsChecker(
+            [
+                Pixel(Point::new(0, 0), Rgb888::RED),
+                Pixel(Point::new(0, 2), Rgb888::BLACK),
+                Pixel(Point::new(0, 1), Rgb888::GREEN),
+                Pixel(Point::new(0, 3), Rgb888::BLACK),
+            ]
+            .into_iter(),
+        );
+        frame
+            .draw_with_background(&mut checker, Rgb888::BLACK)
+            .unwrap();
+        assert_eq!(checker.0.next(), None);
+
+        let mut out = [0xff; 4];
+        assert_eq!(frame.decode_indices(&mut out), Ok(()));
+        assert_eq!(out, [1, 2, 0xff, 0xff]);
+
+        // truncated after rows 0 and 2
+        let data = b"GIF89a\x01\x00\x04\x00\x81\x00\x00\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x2c\x00\x00\x00\x00\x01\x00\x04\x00\x40\x02\x02\xcc\x0a\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+        assert_eq!(frame.pixels().count(), 2);
+        assert!(matches!(
+            DecodedFrame::<_, 4>::new(&frame),
+            Err(ParseError::UnexpectedEndOfFile)
+        ));
+        let mut rows = frame.rows::<1>().unwrap();
+        assert_eq!(
+            rows.next(),
+            Some(Ok((0, heapless::Vec::from_slice(&[1]).unwrap())))
+        );
+        assert_eq!(
+            rows.next(),
+            Some(Ok((2, heapless::Vec::from_slice(&[3]).unwrap())))
+        );
+        assert_eq!(rows.next(), Some(Err(ParseError::UnexpectedEndOfFile)));
+        assert_eq!(rows.next(), None);
+    }
+
+    #[test]
+    fn test_interlaced_row() {
+        let rows = [0, 8, 4, 2, 6, 1, 3, 5, 7, 9];
+        for (n, &row) in rows.iter().enumerate() {
+            assert_eq!(interlaced_row(n, 10), row);
+        }
+        assert_eq!(interlaced_row(0, 1), 0);
+        assert_eq!(interlaced_row(1, 2), 1);
+    }
+
+    #[test]
+    fn test_color_table_nearest() {
+        let table = ColorTable::new(b"\x00\x00\x00\xff\xff\xff\xff\x00\x00");
+        assert_eq!(table.nearest(Rgb888::new(10, 20, 30)), 0);
+        assert_eq!(table.nearest(Rgb888::new(200, 180, 190)), 1);
+        assert_eq!(table.nearest(Rgb888::new(200, 30, 20)), 2);
+        assert_eq!(ColorTable::new(&[]).nearest(Rgb888::new(1, 2, 3)), 0);
+    }
+
+    #[test]
+    fn test_color_table_from_rgb888_slice() {
+        let table = ColorTable::from_rgb888_slice(&[0xff, 0, 0, 0, 0xff, 0]).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get(1), Some(Rgb888::GREEN));
+        assert_eq!(table.get(2), None);
+        assert_eq!(
+            ColorTable::from_rgb888_slice(&[0xff, 0]),
+            Err(ParseError::InvalidColorTableLength)
+        );
+        assert_eq!(
+            ColorTable::from_rgb888_slice(&[0; 257 * 3]),
+            Err(ParseError::InvalidColorTableLength)
+        );
+    }
+
+    #[test]
+    fn test_color_table_get_unchecked() {
+        let table = ColorTable::new(b"\x00\x00\x00\xff\xff\xff\xff\x00\x00");
+        for index in 0..3 {
+            assert_eq!(
+                Some(unsafe { table.get_unchecked(index) }),
+                table.get(index)
+            );
+        }
+    }
+
+    #[test]
+    fn test_color_table_colors_eq() {
+        let data = b"\x00\x00\x00\xff\xff\xff";
+        let table = ColorTable::new(data);
+        assert!(table.colors_eq(&ColorTable::new(data).with_sorted(true)));
+        assert!(table.colors_eq(&ColorTable::new(&[0, 0, 0, 255, 255, 255])));
+        assert!(!table.colors_eq(&ColorTable::new(&data[..3])));
+        assert!(!table.colors_eq(&ColorTable::new(b"\x00\x00\x00\xff\xff\xfe")));
+    }
+
+    #[test]
+    fn test_color_table_to_rgb565_lut() {
+        let table = ColorTable::new(b"\x00\x00\x00\xff\xff\xff\xff\x00\x00");
+        assert_eq!(
+            table.to_rgb565_lut(),
+            [Rgb565::BLACK, Rgb565::WHITE, Rgb565::RED]
+        );
+    }
+
+    #[test]
+    fn test_draw_without_color_table() {
+        let data = b"GIF87a\x01\x00\x01\x00\x00\x00\x00\x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x44\x01\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+
+        let mut counter = PixelCounter(0);
+        assert_eq!(frame.draw(&mut counter), Ok(()));
+        assert_eq!(
+            frame.try_draw(&mut counter),
+            Err(DrawError::Parse(ParseError::MissingColorTable))
+        );
+        assert_eq!(counter.0, 0);
+
+        // a palette override doesn't need a color table
+        assert_eq!(
+            frame.draw_with_palette(&mut counter, &[Rgb888::GREEN]),
+            Ok(())
+        );
+        assert_eq!(counter.0, 1);
+    }
+
+    #[test]
+    fn test_missing_end_code() {
+        // 1x2 screen, 1x1 image without end code, the zero padding bits decode as more codes
+        let data = b"GIF87a\x01\x00\x02\x00\x80\x01\x00\xff\x00\x00\x00\xff\x00\
+            \x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x04\x00\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+
+        let mut out = [1; 2];
+        assert_eq!(frame.decode_indices(&mut out), Ok(()));
+        assert_eq!(out, [0, 1]);
+        assert_eq!(frame.pixels().count(), 1);
+        let mut counter = PixelCounter(0);
+        assert_eq!(frame.try_draw(&mut counter), Ok(()));
+        assert_eq!(counter.0, 1);
+    }
+
+    #[test]
+    fn test_image_block_exceeding_screen() {
+        assert_eq!(
+            Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap().validate(),
+            Ok(())
+        );
+
+        // 2x1 image block on a 1x1 logical screen
+        let data = b"GIF87a\x01\x00\x01\x00\x80\x00\x00\xff\x00\x00\x00\x00\x00\x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x4c\x0a\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        assert_eq!(gif.validate(), Err(ParseError::InvalidImageDimensions));
+
+        let frame = gif.first_frame().unwrap();
+        assert!(!frame.is_full_opaque());
+        assert_eq!((frame.frame_left(), frame.frame_top()), (0, 0));
+        assert_eq!((frame.frame_width(), frame.frame_height()), (2, 1));
+        let mut counter = PixelCounter(0);
+        assert_eq!(frame.draw(&mut counter), Ok(()));
+        assert_eq!(counter.0, 1);
+        assert_eq!(frame.pixels().count(), 1);
+        assert_eq!(frame.used_color_count(), 1);
+        let decoded = DecodedFrame::<_, 2>::new(&frame).unwrap();
+        let mut counter = PixelCounter(0);
+        assert_eq!(decoded.draw(&mut counter), Ok(()));
+        assert_eq!(counter.0, 1);
+    }
+
+    #[test]
+    fn test_draw_rotated() {
+        // 2x1 logical screen, red then green
+        let data = b"GIF87a\x02\x00\x01\x00\x81\x00\x00\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x8c\x0a\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+        for (rotation, red, green) in [
+            (Rotation::Deg0, (0, 0), (1, 0)),
+            (Rotation::Deg90, (0, 0), (0, 1)),
+            (Rotation::Deg180, (1, 0), (0, 0)),
+            (Rotation::Deg270, (0, 1), (0, 0)),
+        ] {
+            let mut checker = PixelsChecker(
+                [
+                    Pixel(Point::new(red.0, red.1), Rgb888::RED),
+                    Pixel(Point::new(green.0, green.1), Rgb888::GREEN),
+                ]
+                .into_iter(),
+            );
+            frame.draw_rotated(&mut checker, rotation).unwrap();
+            assert_eq!(checker.0.next(), None);
+        }
+    }
+
+    #[test]
+    fn test_draw_sub_image_local() {
+        // 3x1 logical screen, 2x1 image block at x = 1, red then green
+        let data = b"GIF87a\x03\x00\x01\x00\x81\x00\x00\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x2c\x01\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x8c\x0a\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+        let mut checker = PixelsChecker([Pixel(Point::new(2, 0), Rgb888::GREEN)].into_iter());
+        let area = Rectangle::new(Point::new(1, 0), Size::new(2, 1));
+        frame.draw_sub_image_local(&mut checker, &area).unwrap();
+        assert_eq!(checker.0.next(), None);
+    }
+
+    #[test]
+    fn test_transparent_index_valid() {
+        // 1x1 logical screen, 2 entries in the global color table, transparent index 1
+        let mut data = *b"GIF89a\x01\x00\x01\x00\x80\x00\x00\
+            \xff\x00\x00\x00\x00\x00\
+            \x21\xf9\x04\x01\x00\x00\x01\x00\
+            \x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x44\x01\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(&data).unwrap();
+        assert!(gif.first_frame().unwrap().transparent_index_valid());
+        assert_eq!(gif.validate(), Ok(()));
+
+        data[25] = 2;
+        let gif = Gif::<Rgb888>::from_slice(&data).unwrap();
+        let frame = gif.first_frame().unwrap();
+        assert!(!frame.transparent_index_valid());
+        assert_eq!(gif.validate(), Err(ParseError::InvalidColorIndex));
+        // the index never matches, the pixel is drawn
+        let mut counter = PixelCounter(0);
+        frame.draw(&mut counter).unwrap();
+        assert_eq!(counter.0, 1);
+    }
+
+    #[test]
+    fn test_frame_decoder() {
+        let gif =
+            Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-240x240.gif")).unwrap();
+        let frame = gif.frames().nth(1).unwrap();
+        let block = frame.bounding_box();
+        let mut decoder = frame.decoder().unwrap();
+        let mut index_pixels = frame.index_pixels();
+        while let Some(DecodedChunk {
+            x,
+            y,
+            indices: decoded,
+        }) = decoder.decode_next().unwrap()
+        {
+            assert!(!decoded.is_empty());
+            let start = (u32::from(y) - block.top_left.y as u32) * block.size.width
+                + (u32::from(x) - block.top_left.x as u32);
+            for (i, &color_index) in decoded.iter().enumerate() {
+                let idx = start + i as u32;
+                let x = block.top_left.x as u32 + idx % block.size.width;
+                let y = block.top_left.y as u32 + idx / block.size.width;
+                assert_eq!(index_pixels.next(), Some((x as u16, y as u16, color_index)));
+            }
+        }
+        assert_eq!(index_pixels.next(), None);
+    }
+
+    #[test]
+    fn test_multiple_image_blocks() {
+        // 2x1 logical screen, one frame of two 1x1 image blocks, red at x = 0 then green
+        // at x = 1
+        let data = b"GIF89a\x02\x00\x01\x00\x81\x00\x00\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x21\xf9\x04\x00\x0a\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x4c\x01\x00\
+            \x2c\x01\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x54\x01\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        assert_eq!(gif.frame_count(), 1);
+        assert_eq!(gif.validate(), Ok(()));
+        let frame = gif.first_frame().unwrap();
+        assert_eq!(frame.delay_centis, 10);
+
+        let expected = [
+            Pixel(Point::new(0, 0), Rgb888::RED),
+            Pixel(Point::new(1, 0), Rgb888::GREEN),
+        ];
+        let mut checker = PixelsChecker(expected.into_iter());
+        frame.draw(&mut checker).unwrap();
+        assert_eq!(checker.0.next(), None);
+        assert!(frame.pixels().eq(expected));
+        assert!(frame.index_pixels().eq([(0, 0, 1), (1, 0, 2)]));
+    }
+
+    #[test]
+    fn test_thumbnail() {
+        // 2x1 logical screen, red then green
+        let data = b"GIF87a\x02\x00\x01\x00\x81\x00\x00\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x8c\x0a\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let mut out = [Rgb888::BLACK; 1];
+        gif.thumbnail(&mut out, 1, 1).unwrap();
+        assert_eq!(out, [Rgb888::RED]);
+
+        let mut out = [Rgb888::BLACK; 8];
+        gif.thumbnail(&mut out, 4, 2).unwrap();
+        let (r, g) = (Rgb888::RED, Rgb888::GREEN);
+        assert_eq!(out, [r, r, g, g, r, r, g, g]);
+
+        assert_eq!(
+            gif.thumbnail(&mut out, 2, 2),
+            Err(ParseError::BufferTooSmall)
+        );
+
+        let gif =
+            Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-240x240.gif")).unwrap();
+        let mut out = [Rgb888::BLACK; 24 * 24];
+        gif.thumbnail(&mut out, 24, 24).unwrap();
+        let mut rgb = [0; 240 * 240 * 3];
+        gif.first_frame().unwrap().decode_rgb888(&mut rgb).unwrap();
+        for (i, color) in out.iter().enumerate() {
+            let j = 3 * ((i / 24) * 10 * 240 + (i % 24) * 10);
+            assert_eq!(*color, Rgb888::new(rgb[j], rgb[j + 1], rgb[j + 2]));
+        }
+    }
+
+    #[test]
+    fn test_draw_sub_image_tracked() {
+        // 2x3 logical screen, transparent index 0 in the first and last rows, red then
+        // green in the middle row
+        let data = b"GIF89a\x02\x00\x03\x00\x81\x00\x00\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x21\xf9\x04\x01\x00\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x02\x00\x03\x00\x00\x02\x04\x04\x22\x00\x05\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+        let mut counter = PixelCounter(0);
+        let area = Rectangle::new(Point::new(1, 0), Size::new(4, 4));
+        assert_eq!(
+            frame.draw_sub_image_tracked(&mut counter, &area),
+            Ok(Rectangle::new(Point::new(1, 1), Size::new(1, 1)))
+        );
+        assert_eq!(counter.0, 1);
+
+        let area = Rectangle::new(Point::new(0, 2), Size::new(2, 1));
+        assert_eq!(
+            frame.draw_sub_image_tracked(&mut counter, &area),
+            Ok(Rectangle::zero())
+        );
+
+        // 4x3 logical screen, one pixel image blocks at the top left and bottom right
+        let data = b"GIF89a\x04\x00\x03\x00\x80\x00\x00\x00\x00\x00\xff\x00\x00\
+            \x21\xf9\x04\x00\x00\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x4c\x01\x00\
+            \x2c\x03\x00\x02\x00\x01\x00\x01\x00\x00\x02\x02\x4c\x01\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+        let area = Rectangle::new(Point::zero(), Size::new(4, 3));
+        assert_eq!(
+            frame.draw_sub_image_tracked(&mut counter, &area),
+            Ok(Rectangle::new(Point::zero(), Size::new(4, 3)))
+        );
+        let area = Rectangle::new(Point::new(2, 0), Size::new(2, 3));
+        assert_eq!(
+            frame.draw_sub_image_tracked(&mut counter, &area),
+            Ok(Rectangle::new(Point::new(3, 2), Size::new(1, 1)))
+        );
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let gif =
+            Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-240x240.gif")).unwrap();
+        let mut frames = gif.frames();
+        let (first, second) = (frames.next().unwrap(), frames.next().unwrap());
+        assert_eq!(first.content_hash(), first.content_hash());
+        assert_ne!(first.content_hash(), second.content_hash());
+
+        // FNV-1a of the 1x1 area at the origin, then index 0
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        let hash = [0, 0, 0, 0, 1, 0, 1, 0, 0]
+            .iter()
+            .fold(0x811c_9dc5u32, |hash, &byte| {
+                (hash ^ byte).wrapping_mul(0x0100_0193)
+            });
+        assert_eq!(gif.first_frame().unwrap().content_hash(), hash);
+    }
+
+    #[test]
+    fn test_draw_at_origin() {
+        // 3x1 logical screen, 2x1 image block at x = 1, red then green
+        let data = b"GIF87a\x03\x00\x01\x00\x81\x00\x00\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x2c\x01\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x8c\x0a\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let mut checker = PixelsChecker(
+            [
+                Pixel(Point::new(0, 0), Rgb888::RED),
+                Pixel(Point::new(1, 0), Rgb888::GREEN),
+            ]
+            .into_iter(),
+        );
+        gif.first_frame()
+            .unwrap()
+            .draw_at_origin(&mut checker)
+            .unwrap();
+        assert_eq!(checker.0.next(), None);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_draw_async() {
+        use core::future::Future;
+        use core::task::{Context, Poll, Waker};
+
+        // Pending once, then ready
+        struct YieldNow(bool);
+
+        impl Future for YieldNow {
+            type Output = ();
+
+            fn poll(mut self: core::pin::Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+                if self.0 {
+                    Poll::Ready(())
+                } else {
+                    self.0 = true;
+                    Poll::Pending
+                }
+            }
+        }
+
+        let gif =
+            Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-240x240.gif")).unwrap();
+        let frame = gif.frames().nth(1).unwrap();
+        let mut expected = PixelCounter(0);
+        frame.draw(&mut expected).unwrap();
+
+        let mut counter = PixelCounter(0);
+        let mut yields = 0;
+        let mut pending = 0;
+        {
+            let mut future = core::pin::pin!(frame.draw_async(&mut counter, 16, || {
+                yields += 1;
+                YieldNow(false)
+            }));
+            let mut cx = Context::from_waker(Waker::noop());
+            while future.as_mut().poll(&mut cx).is_pending() {
+                pending += 1;
+            }
+        }
+        assert_eq!(pending, yields);
+        assert!(yields > 0);
+        assert_eq!(counter.0, expected.0);
+    }
+
+    #[test]
+    fn test_is_optimized_diff() {
+        // 2x1 logical screen, a full frame then 1x1 updates at x = 1 and x = 0, all left
+        // in place
+        let mut data = *b"GIF89a\x02\x00\x01\x00\x81\x00\x00\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x21\xf9\x04\x04\x0a\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x8c\x0a\x00\
+            \x21\xf9\x04\x04\x0a\x00\x00\x00\
+            \x2c\x01\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x4c\x01\x00\
+            \x21\xf9\x04\x08\x0a\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x4c\x01\x00\x3b";
+        assert!(Gif::<Rgb888>::from_slice(&data)
+            .unwrap()
+            .is_optimized_diff());
+
+        // the first frame is restored to the background
+        data[28] = 0x08;
+        assert!(!Gif::<Rgb888>::from_slice(&data)
+            .unwrap()
+            .is_optimized_diff());
+
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        assert!(!gif.is_optimized_diff());
+    }
+
+    #[test]
+    fn test_decode_indices_4bpp() {
+        // 3x1 logical screen, 2x1 image block at x = 1 with indices 1 and 2
+        let data = b"GIF87a\x03\x00\x01\x00\x81\x00\x00\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x2c\x01\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x8c\x0a\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+        let mut out = [0xff; 2];
+        assert_eq!(frame.decode_indices_4bpp(&mut out), Ok(()));
+        assert_eq!(out, [0xf1, 0x2f]);
+        assert_eq!(
+            frame.decode_indices_4bpp(&mut out[..1]),
+            Err(ParseError::BufferTooSmall)
+        );
+
+        // 1x1, minimum code size 5, index 16
+        let data = b"GIF87a\x01\x00\x01\x00\x80\x00\x00\x00\x00\x00\xff\xff\xff\
+            \x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x05\x03\x20\x14\x02\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+        assert_eq!(
+            frame.decode_indices_4bpp(&mut out),
+            Err(ParseError::InvalidColorIndex)
+        );
+    }
+
+    #[test]
+    fn test_draw_over() {
+        // 2x1 screen, frame 0 is green then red and is left in place, frame 1 is
+        // transparent then red
+        let data = b"GIF89a\x02\x00\x01\x00\x81\x00\x00\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x21\xf9\x04\x04\x0a\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x54\x0a\x00\
+            \x21\xf9\x04\x01\x0a\x00\x03\x00\
+            \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\x5c\x0a\x00\
+            \x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let mut frames = gif.frames();
+        let frame0 = frames.next().unwrap();
+        let frame1 = frames.next().unwrap();
+
+        let prev = DecodedFrame::<_, 2>::new(&frame0).unwrap();
+        let mut checker = PixelsChecker(
+            [
+                Pixel(Point::new(0, 0), Rgb888::GREEN),
+                Pixel(Point::new(1, 0), Rgb888::RED),
+            ]
+            .into_iter(),
+        );
+        frame1.draw_over(&mut checker, &prev).unwrap();
+        assert_eq!(checker.0.next(), None);
+
+        // no global color table, frame 0 is green then red with a local color table,
+        // frame 1 has no color table for its red pixel, then a transparent one
+        let data = b"GIF89a\x02\x00\x01\x00\x00\x00\x00\
+            \x21\xf9\x04\x04\x0a\x00\x00\x00\
+            \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x81\
+            \x00\x00\x00\xff\x00\x00\x00\xff\x00\x00\x00\xff\
+            \x02\x02\x54\x0a\x00\
+            \x21\xf9\x04\x01\x0a\x00\x03\x00\
+            \x2c\x00\x00\x00\x00\x02\x00\x01\x00\x00\x02\x02\xcc\x0a\x00\
+            \x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let mut frames = gif.frames();
+        let frame0 = frames.next().unwrap();
+        let frame1 = frames.next().unwrap();
+
+        let prev = DecodedFrame::<_, 2>::new(&frame0).unwrap();
+        let mut checker = PixelsChecker([Pixel(Point::new(1, 0), Rgb888::RED)].into_iter());
+        frame1.draw_over(&mut checker, &prev).unwrap();
+        assert_eq!(checker.0.next(), None);
+    }
+
+    #[test]
+    fn test_image_block_at_u16_boundary() {
+        // 1x2 image block at (65535, 65535) on a 1x1 logical screen
+        let data = b"GIF87a\x01\x00\x01\x00\x80\x00\x00\xff\x00\x00\x00\x00\x00\
+            \x2c\xff\xff\xff\xff\x01\x00\x02\x00\x00\x02\x02\x4c\x0a\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+        assert_eq!(
+            frame.bounding_box(),
+            Rectangle::new(Point::new(65535, 65535), Size::new(1, 2))
+        );
+
+        let mut counter = PixelCounter(0);
+        assert_eq!(frame.try_draw(&mut counter), Ok(()));
+        assert_eq!(counter.0, 0);
+        assert_eq!(frame.pixels().count(), 0);
+        let mut out = [1];
+        assert_eq!(frame.decode_indices(&mut out), Ok(()));
+        assert_eq!(out, [1]);
+        let rows: heapless::Vec<_, 2> = frame
+            .rows::<1>()
+            .unwrap()
+            .map(|row| row.unwrap().0)
+            .collect();
+        assert_eq!(rows, [65535, 65535]);
+    }
+
+    #[test]
+    fn test_unsupported_extension() {
+        assert_eq!(
+            ExtensionBlock::parse(b"\x42\x00"),
+            Err(ParseError::UnsupportedExtension(0x42))
+        );
+    }
+
+    #[test]
+    fn test_invalid_color_index() {
+        // color index 3 with a 2 entry global color table
+        let data = b"GIF87a\x01\x00\x01\x00\x80\x00\x00\xff\x00\x00\x00\x00\x00\x2c\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x5c\x01\x00\x3b";
+        let gif = Gif::<Rgb888>::from_slice(data).unwrap();
+        let frame = gif.first_frame().unwrap();
+
+        let mut counter = PixelCounter(0);
+        assert_eq!(frame.draw(&mut counter), Ok(()));
+        assert_eq!(
+            frame.try_draw(&mut counter),
+            Err(DrawError::Parse(ParseError::InvalidColorIndex))
+        );
+        assert_eq!(counter.0, 0);
+    }
+
+    #[cfg(feature = "framebuffer")]
+    #[test]
+    fn test_to_framebuffer() {
+        use embedded_graphics::framebuffer::buffer_size;
+        use embedded_graphics::image::GetPixel;
+        use embedded_graphics::pixelcolor::raw::LittleEndian;
+
+        let gif = Gif::<Rgb888>::from_slice(GIF87A_1X1).unwrap();
+        let frame = gif.first_frame().unwrap();
+        let framebuffer: Framebuffer<_, _, LittleEndian, 2, 1, { buffer_size::<Rgb888>(2, 1) }> =
+            frame.to_framebuffer();
+        assert_eq!(framebuffer.pixel(Point::new(0, 0)), Some(Rgb888::RED));
+        assert_eq!(framebuffer.pixel(Point::new(1, 0)), Some(Rgb888::BLACK));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_owned_gif() {
+        let data = alloc::vec::Vec::from(GIF87A_1X1);
+        let gif = OwnedGif::<Rgb888>::from_slice(&data).unwrap();
+        drop(data);
+
+        assert_eq!(gif.size(), Size::new(1, 1));
+        assert_eq!(gif.global_color_table().map(|table| table.len()), Some(2));
+        assert_eq!(gif.frame_count(), 1);
+        let frame = gif.first_frame().unwrap();
+        assert_eq!(
+            frame.pixels().next(),
+            Some(Pixel(Point::new(0, 0), Rgb888::new(0xff, 0, 0)))
+        );
+    }
 }
@@ -11,6 +11,39 @@ type Code = u16;
 // Do not use Option<Code>, to save memory
 const CODE_NONE: Code = u16::MAX;
 
+/// A dictionary entry, packed in 32 bits to keep the table small: the last byte of the
+/// string in bits 0..8, the code of the prefix in bits 8..20, and the length of the string
+/// in bits 20..32.
+///
+/// Codes are at most `MAX_CODESIZE` bits, and as a prefix is always an earlier entry,
+/// strings are shorter than `MAX_ENTRIES`.
+#[derive(Debug, Clone, Copy)]
+struct Entry(u32);
+
+impl Entry {
+    const MAX_LEN: usize = MAX_ENTRIES - 1;
+
+    #[inline(always)]
+    fn new(prefix: Code, byte: u8, len: usize) -> Self {
+        Entry(byte as u32 | (prefix as u32 & 0xfff) << 8 | (len as u32) << 20)
+    }
+
+    #[inline(always)]
+    fn byte(self) -> u8 {
+        self.0 as u8
+    }
+
+    #[inline(always)]
+    fn prefix(self) -> usize {
+        (self.0 >> 8) as usize & 0xfff
+    }
+
+    #[inline(always)]
+    fn len(self) -> usize {
+        (self.0 >> 20) as usize
+    }
+}
+
 /// Decoding dictionary.
 ///
 /// It is not generic due to current limitations of Rust
@@ -18,12 +51,49 @@ const CODE_NONE: Code = u16::MAX;
 #[derive(Debug)]
 pub(crate) struct DecodingDict {
     min_size: u8,
-    #[cfg(feature = "8k")]
-    table: heapless::Vec<(Code, u8), 8192>,
+    #[cfg(feature = "alloc")]
+    table: alloc::vec::Vec<Entry>,
+    #[cfg(all(not(feature = "alloc"), feature = "8k"))]
+    table: heapless::Vec<Entry, 8192>,
     // FIXME: 4096 is not sufficient for some gifs
-    #[cfg(not(feature = "8k"))]
-    table: heapless::Vec<(Code, u8), 4096>,
-    buffer: heapless::Vec<u8, 1024>,
+    #[cfg(all(not(feature = "alloc"), not(feature = "8k")))]
+    table: heapless::Vec<Entry, 4096>,
+    #[cfg(feature = "alloc")]
+    buffer: alloc::vec::Vec<u8>,
+    // the longest string, plus the byte appended by `extend_buffer`
+    #[cfg(not(feature = "alloc"))]
+    buffer: heapless::Vec<u8, { Entry::MAX_LEN + 1 }>,
+}
+
+/// Pushes to a fixed capacity buffer, fails with `DictionaryOverflow` when it is full.
+#[cfg(not(feature = "alloc"))]
+#[inline(always)]
+fn push<T, const N: usize>(vec: &mut heapless::Vec<T, N>, value: T) -> Result<(), ParseError> {
+    vec.push(value).map_err(|_| ParseError::DictionaryOverflow)
+}
+
+/// Pushes to a growable buffer.
+#[cfg(feature = "alloc")]
+#[inline(always)]
+fn push<T>(vec: &mut alloc::vec::Vec<T>, value: T) -> Result<(), ParseError> {
+    vec.push(value);
+    Ok(())
+}
+
+/// Resizes a fixed capacity buffer, fails with `DictionaryOverflow` when it is too small.
+#[cfg(not(feature = "alloc"))]
+#[inline(always)]
+fn resize<const N: usize>(vec: &mut heapless::Vec<u8, N>, len: usize) -> Result<(), ParseError> {
+    vec.resize(len, 0)
+        .map_err(|_| ParseError::DictionaryOverflow)
+}
+
+/// Resizes a growable buffer.
+#[cfg(feature = "alloc")]
+#[inline(always)]
+fn resize(vec: &mut alloc::vec::Vec<u8>, len: usize) -> Result<(), ParseError> {
+    vec.resize(len, 0);
+    Ok(())
 }
 
 impl DecodingDict {
@@ -31,61 +101,71 @@ impl DecodingDict {
     pub fn new(min_size: u8) -> DecodingDict {
         DecodingDict {
             min_size,
-            table: heapless::Vec::new(),
-            buffer: heapless::Vec::new(),
+            table: Default::default(),
+            buffer: Default::default(),
         }
     }
 
+    /// Changes the minimum code size, the dictionary is emptied until the next reset
+    pub fn set_min_size(&mut self, min_size: u8) {
+        self.min_size = min_size;
+        self.table.clear();
+        self.buffer.clear();
+    }
+
     /// Resets the dictionary
-    pub fn reset(&mut self) {
+    pub fn reset(&mut self) -> Result<(), ParseError> {
         self.table.clear();
         for i in 0..(1u16 << self.min_size as usize) {
-            self.table.push((CODE_NONE, i as u8)).unwrap();
+            push(&mut self.table, Entry::new(CODE_NONE, i as u8, 1))?;
         }
+        Ok(())
     }
 
-    /// Inserts a value into the dict
+    /// Inserts a value into the dict, `key` is the code of its prefix
     #[inline(always)]
-    pub fn push(&mut self, key: Code, value: u8) {
-        self.table.push((key, value)).unwrap(); // TODO: overflow check
+    pub fn push(&mut self, key: Code, value: u8) -> Result<(), ParseError> {
+        let len = match self.table.get(key as usize) {
+            Some(prefix) => prefix.len() + 1,
+            None if key == CODE_NONE => 1,
+            None => return Err(ParseError::InvalidByte),
+        };
+        if len > Entry::MAX_LEN {
+            return Err(ParseError::DictionaryOverflow);
+        }
+        push(&mut self.table, Entry::new(key, value, len))
     }
 
     /// Reconstructs the data for the corresponding code
     pub fn reconstruct(&mut self, code: Code) -> Result<&[u8], ParseError> {
-        self.buffer.clear();
-        let mut code = code;
-        let mut cha;
         // Check the first access more thoroughly since a bad code
         // could occur if the data is malformed
-        if code != CODE_NONE {
-            match self.table.get(code as usize) {
-                Some(&(code_, cha_)) => {
-                    code = code_;
-                    cha = cha_;
-                }
-                None => {
-                    return Err(ParseError::InvalidByte); //
-                }
+        let Some(&entry) = self.table.get(code as usize) else {
+            return Err(ParseError::InvalidByte);
+        };
+        // the string is written from its end, following the prefixes
+        let len = entry.len();
+        resize(&mut self.buffer, len)?;
+        let mut entry = entry;
+        for (i, byte) in self.buffer.iter_mut().enumerate().rev() {
+            *byte = entry.byte();
+            if i > 0 {
+                // prefixes are earlier entries, with a length of `i`
+                let Some(&prefix) = self.table.get(entry.prefix()) else {
+                    return Err(ParseError::InvalidByte);
+                };
+                entry = prefix;
             }
-            self.buffer.push(cha).unwrap();
         }
-        while code != CODE_NONE {
-            if self.buffer.len() >= MAX_ENTRIES {
-                return Err(ParseError::InvalidByte); // Invalid code sequence. Cycle in decoding table
-            }
-            //(code, cha) = self.table[k as usize];
-            // Note: This could possibly be replaced with an unchecked array access if
-            //  - value is asserted to be < self.next_code() in push
-            //  - min_size is asserted to be < MAX_CODESIZE
-            let entry = self.table[code as usize];
-            code = entry.0;
-            cha = entry.1;
-            self.buffer.push(cha).unwrap();
-        }
-        self.buffer.reverse();
         Ok(&self.buffer)
     }
 
+    /// Appends a byte to the data of the last reconstruction
+    #[inline(always)]
+    pub fn extend_buffer(&mut self, byte: u8) -> Result<(), ParseError> {
+        push(&mut self.buffer, byte)
+    }
+
     /// Returns the buffer constructed by the last reconstruction
     #[inline(always)]
     pub fn buffer(&self) -> &[u8] {
@@ -97,6 +177,46 @@ impl DecodingDict {
     pub fn next_code(&self) -> u16 {
         self.table.len() as u16
     }
+
+    /// Drops the entries added after the first `len` ones
+    pub fn truncate(&mut self, len: u16) {
+        self.table.truncate(len as usize);
+    }
+}
+
+/// Where the data decoded by the last call of `Decoder::decode_next` is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    Empty,
+    Single,
+    Dict,
+}
+
+/// A saved decoding position of a `Decoder`, to rewind it with `Decoder::restore`.
+///
+/// The dictionary is not copied, only its length. The state is `Copy` if the reader is.
+#[derive(Debug, Clone, Copy)]
+pub struct DecoderState<I: Iterator<Item = u8>> {
+    bs: BitStream<I>,
+    prev: Code,
+    buf: [u8; 1],
+    output: Output,
+    done: bool,
+    code_size: u8,
+    next_code: u16,
+    resets: u32,
+}
+
+/// Counters of a `Decoder`, accumulated since it was created.
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodeStats {
+    /// Number of clear codes.
+    pub resets: u32,
+    /// Largest code size, in bits.
+    pub max_code_size: u8,
+    /// Number of codes read, including clear and end codes.
+    pub codes: u32,
 }
 
 pub struct Decoder<I: Iterator<Item = u8>> {
@@ -104,17 +224,29 @@ pub struct Decoder<I: Iterator<Item = u8>> {
     prev: Code,
     table: DecodingDict,
     buf: [u8; 1],
+    output: Output,
+    // end code reached
+    done: bool,
+    // number of clear codes seen, to detect stale states
+    resets: u32,
     code_size: u8,
     min_code_size: u8,
     clear_code: Code,
     end_code: Code,
+    #[cfg(feature = "stats")]
+    stats: DecodeStats,
 }
 
 impl<I> Decoder<I>
 where
     I: Iterator<Item = u8>,
 {
+    /// Creates a decoder reading codes from `r`.
+    ///
+    /// A `min_code_size` above 12 is treated as 12, the data then fails to decode with
+    /// `ParseError::DictionaryOverflow` instead of overflowing the codes.
     pub fn new(r: I, min_code_size: u8) -> Decoder<I> {
+        let min_code_size = min_code_size.min(MAX_CODESIZE);
         let clear_code = 1 << min_code_size;
         let end_code = clear_code + 1;
         let table = DecodingDict::new(min_code_size);
@@ -123,55 +255,276 @@ where
             prev: CODE_NONE,
             table,
             buf: [0],
+            output: Output::Empty,
+            done: false,
+            resets: 0,
             code_size: min_code_size + 1,
             min_code_size,
             clear_code,
             end_code,
+            #[cfg(feature = "stats")]
+            stats: DecodeStats {
+                max_code_size: min_code_size + 1,
+                ..DecodeStats::default()
+            },
+        }
+    }
+
+    /// Starts decoding new data from `r`, reusing the dictionary storage.
+    pub fn reset(&mut self, r: I, min_code_size: u8) {
+        let min_code_size = min_code_size.min(MAX_CODESIZE);
+        self.bs = BitStream::new(r);
+        self.prev = CODE_NONE;
+        self.table.set_min_size(min_code_size);
+        self.output = Output::Empty;
+        self.done = false;
+        // states saved from the previous data are stale
+        self.resets = self.resets.wrapping_add(1);
+        self.code_size = min_code_size + 1;
+        self.min_code_size = min_code_size;
+        #[cfg(feature = "stats")]
+        {
+            self.stats.max_code_size = self.stats.max_code_size.max(self.code_size);
         }
+        self.clear_code = 1 << min_code_size;
+        self.end_code = self.clear_code + 1;
     }
 
     pub fn decode_next(&mut self) -> Result<Option<&[u8]>, ParseError> {
+        if self.done {
+            return Ok(None);
+        }
         let code = match self.bs.next_bits(self.code_size) {
-            Some(code) => code,
+            Some(code) => code as Code,
             None => return Ok(None), // end of stream
         };
+        #[cfg(feature = "stats")]
+        {
+            self.stats.codes += 1;
+            self.stats.resets += u32::from(code == self.clear_code);
+        }
 
         if code == self.clear_code {
-            self.table.reset();
-            self.table.push(CODE_NONE, 0); // clear code
-            self.table.push(CODE_NONE, 0); // end code
+            self.table.reset()?;
+            self.resets = self.resets.wrapping_add(1);
+            self.table.push(CODE_NONE, 0)?; // clear code
+            self.table.push(CODE_NONE, 0)?; // end code
             self.code_size = self.min_code_size + 1;
             self.prev = CODE_NONE;
-            Ok(Some(&[]))
+            self.output = Output::Empty;
         } else if code == self.end_code {
-            Ok(Some(&[]))
+            // ignore the padding bits after the end code
+            self.done = true;
+            return Ok(None);
         } else {
             let next_code = self.table.next_code();
             if code > next_code {
                 return Err(ParseError::InvalidByte); // invalid code 9bit, should be LE next_code
             }
             let prev = self.prev;
-            let result = if prev == CODE_NONE {
+            if prev == CODE_NONE {
                 self.buf = [code as u8];
-                &self.buf[..]
+                self.output = Output::Single;
             } else {
-                if code == next_code {
-                    let chr = self.table.reconstruct(prev)?[0];
-                    self.table.push(prev, chr);
-                    self.table.reconstruct(code)?
-                } else if code < next_code {
-                    let chr = self.table.reconstruct(code)?[0];
-                    self.table.push(prev, chr);
-                    self.table.buffer()
-                } else {
-                    unreachable!("checked above")
+                // the string of a code equal to `next_code` is the one of `prev`, followed by
+                // its first byte
+                let known = code < next_code;
+                let decoded = self.table.reconstruct(if known { code } else { prev })?;
+                let Some(&chr) = decoded.first() else {
+                    return Err(ParseError::InvalidByte);
+                };
+                self.table.push(prev, chr)?;
+                if !known {
+                    self.table.extend_buffer(chr)?;
                 }
+                self.output = Output::Dict;
             };
             if next_code == (1 << self.code_size as usize) - 1 && self.code_size < MAX_CODESIZE {
                 self.code_size += 1;
+                #[cfg(feature = "stats")]
+                {
+                    self.stats.max_code_size = self.stats.max_code_size.max(self.code_size);
+                }
             }
             self.prev = code;
-            Ok(Some(result))
         }
+        Ok(Some(self.buffer()))
+    }
+
+    /// Saves the decoding position, the reader is cloned.
+    pub fn state(&self) -> DecoderState<I>
+    where
+        I: Clone,
+    {
+        DecoderState {
+            bs: self.bs.clone(),
+            prev: self.prev,
+            buf: self.buf,
+            output: self.output,
+            done: self.done,
+            code_size: self.code_size,
+            next_code: self.table.next_code(),
+            resets: self.resets,
+        }
+    }
+
+    /// Rewinds to a position saved by `state`, decoding continues from there.
+    ///
+    /// Entries added to the dictionary since are dropped. As a clear code rebuilds the
+    /// dictionary, `ParseError::StaleDecoderState` is returned if one was decoded since
+    /// the state was saved.
+    pub fn restore(&mut self, state: DecoderState<I>) -> Result<(), ParseError> {
+        if state.resets != self.resets {
+            return Err(ParseError::StaleDecoderState);
+        }
+        self.table.truncate(state.next_code);
+        self.bs = state.bs;
+        self.prev = state.prev;
+        self.buf = state.buf;
+        self.output = state.output;
+        self.done = state.done;
+        self.code_size = state.code_size;
+        if self.output == Output::Dict {
+            // the last decoded data is the string of the last code
+            self.table.reconstruct(self.prev)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the counters accumulated since the decoder was created.
+    #[cfg(feature = "stats")]
+    pub fn stats(&self) -> DecodeStats {
+        self.stats
+    }
+
+    /// Returns the data decoded by the last call of `decode_next`.
+    #[inline(always)]
+    pub fn buffer(&self) -> &[u8] {
+        match self.output {
+            Output::Empty => &[],
+            Output::Single => &self.buf,
+            Output::Dict => self.table.buffer(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(data: &[u8], min_code_size: u8) -> heapless::Vec<u8, 64> {
+        let mut decoder = Decoder::new(data.iter().copied(), min_code_size);
+        let mut output = heapless::Vec::new();
+        while let Some(decoded) = decoder.decode_next().unwrap() {
+            output.extend_from_slice(decoded).unwrap();
+        }
+        output
+    }
+
+    #[test]
+    fn test_clear_code_mid_stream() {
+        // codes: clear, 1, 2, 6, clear (4 bits after the code size increase), 3, 6, end
+        let data = [0x8c, 0x4c, 0x73, 0x01];
+        assert_eq!(decode_all(&data, 2), [1, 2, 1, 2, 3, 3, 3]);
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn test_stats() {
+        let data = [0x8c, 0x4c, 0x73, 0x01];
+        let mut decoder = Decoder::new(data.iter().copied(), 2);
+        while decoder.decode_next().unwrap().is_some() {}
+        assert_eq!(
+            decoder.stats(),
+            DecodeStats {
+                resets: 2,
+                max_code_size: 4,
+                codes: 8,
+            }
+        );
+    }
+
+    #[test]
+    fn test_missing_end_code() {
+        // codes: clear, 1, 2, 6, 3 (4 bits), the stream ends right after the last code
+        let data = [0x8c, 0x3c];
+        assert_eq!(decode_all(&data, 2), [1, 2, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_overflow() {
+        // min code size 2: clear, then codes each equal to the next code, so that every
+        // string is one byte longer than the previous one
+        let mut codes = heapless::Vec::<(u16, u8), 4096>::new();
+        codes.push((4, 3)).unwrap();
+        codes.push((0, 3)).unwrap();
+        let mut code_size = 3;
+        for next_code in 6..4096 {
+            codes.push((next_code, code_size)).unwrap();
+            if next_code == (1 << code_size) - 1 && code_size < MAX_CODESIZE {
+                code_size += 1;
+            }
+        }
+        let mut data = heapless::Vec::<u8, 8192>::new();
+        let (mut acc, mut nbits) = (0u32, 0);
+        for (code, size) in codes {
+            acc |= u32::from(code) << nbits;
+            nbits += size;
+            while nbits >= 8 {
+                data.push(acc as u8).unwrap();
+                acc >>= 8;
+                nbits -= 8;
+            }
+        }
+        if nbits > 0 {
+            data.push(acc as u8).unwrap();
+        }
+
+        let mut decoder = Decoder::new(data.iter().copied(), 2);
+        let mut max_len = 0;
+        while let Some(decoded) = decoder.decode_next().unwrap() {
+            assert!(decoded.iter().all(|&byte| byte == 0));
+            max_len = max_len.max(decoded.len());
+        }
+        // the last code fills the dictionary, its string is the longest one
+        assert_eq!(max_len, 4091);
+
+        // clamped code size, the first code is past the end of the empty dictionary
+        let mut decoder = Decoder::new([0xff, 0xff, 0xff].into_iter(), 20);
+        assert_eq!(decoder.decode_next(), Err(ParseError::InvalidByte));
+    }
+
+    #[test]
+    fn test_restore_state() {
+        let data = [0x8c, 0x4c, 0x73, 0x01];
+        let mut decoder = Decoder::new(data.iter().copied(), 2);
+        assert_eq!(decoder.decode_next(), Ok(Some(&[][..])));
+        assert_eq!(decoder.decode_next(), Ok(Some(&[1][..])));
+        let state = decoder.state();
+        assert_eq!(decoder.decode_next(), Ok(Some(&[2][..])));
+        assert_eq!(decoder.decode_next(), Ok(Some(&[1, 2][..])));
+
+        decoder.restore(state.clone()).unwrap();
+        assert_eq!(decoder.buffer(), [1]);
+        assert_eq!(decoder.decode_next(), Ok(Some(&[2][..])));
+        assert_eq!(decoder.decode_next(), Ok(Some(&[1, 2][..])));
+
+        // the dictionary is rebuilt after the clear code
+        assert_eq!(decoder.decode_next(), Ok(Some(&[][..])));
+        assert_eq!(decoder.restore(state), Err(ParseError::StaleDecoderState));
+    }
+
+    #[test]
+    fn test_reset() {
+        let data = [0x8c, 0x4c, 0x73, 0x01];
+        let mut decoder = Decoder::new(data.iter().copied(), 2);
+        while decoder.decode_next().unwrap().is_some() {}
+
+        // min code size 2, codes: clear, 0, end
+        let data = [0x44, 0x01];
+        decoder.reset(data.iter().copied(), 2);
+        assert_eq!(decoder.decode_next(), Ok(Some(&[][..])));
+        assert_eq!(decoder.decode_next(), Ok(Some(&[0][..])));
+        assert_eq!(decoder.decode_next(), Ok(None));
     }
 }
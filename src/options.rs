@@ -0,0 +1,281 @@
+//! Options of `Frame::draw_with`, and the target adapters scaling, rotating or tracking
+//! the drawn frames.
+
+use embedded_graphics::prelude::{DrawTarget, OriginDimensions, Point, Size};
+use embedded_graphics::primitives::Rectangle;
+use embedded_graphics::Pixel;
+
+/// Options of `Frame::draw_with`, built from `DrawOptions::new()` with the `with_*` methods.
+///
+/// The default options draw the frame as `draw` does.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DrawOptions<C> {
+    pub(crate) background: Option<C>,
+    pub(crate) use_fast_path: bool,
+    pub(crate) respect_transparency: bool,
+    pub(crate) clip: Option<Rectangle>,
+    pub(crate) scale: (u8, u8),
+}
+
+impl<C> Default for DrawOptions<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> DrawOptions<C> {
+    pub const fn new() -> Self {
+        Self {
+            background: None,
+            use_fast_path: false,
+            respect_transparency: true,
+            clip: None,
+            scale: (1, 1),
+        }
+    }
+
+    /// Draws transparent pixels with `background` instead of skipping them.
+    pub fn with_background(mut self, background: C) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    /// Converts each color table once to a lookup table of `C`, instead of converting
+    /// each drawn pixel.
+    pub const fn with_fast_path(mut self, use_fast_path: bool) -> Self {
+        self.use_fast_path = use_fast_path;
+        self
+    }
+
+    /// If false, transparent pixels are drawn with their color table entry.
+    pub const fn with_transparency(mut self, respect_transparency: bool) -> Self {
+        self.respect_transparency = respect_transparency;
+        self
+    }
+
+    /// Only draws the pixels inside `clip`, in logical screen coordinates.
+    pub const fn with_clip(mut self, clip: Rectangle) -> Self {
+        self.clip = Some(clip);
+        self
+    }
+
+    /// Downscales by `numerator / denominator`, as `Frame::draw_scaled` does.
+    pub const fn with_scale(mut self, numerator: u8, denominator: u8) -> Self {
+        self.scale = (numerator, denominator);
+        self
+    }
+}
+
+// Downscales the pixels drawn on `target` by `numerator / denominator`, using
+// nearest-neighbor sampling.
+pub(crate) struct Scaled<'t, D> {
+    target: &'t mut D,
+    numerator: u32,
+    denominator: u32,
+}
+
+impl<'t, D> Scaled<'t, D> {
+    pub(crate) fn new(target: &'t mut D, numerator: u32, denominator: u32) -> Self {
+        assert!(
+            numerator > 0 && numerator <= denominator,
+            "only downscaling is supported"
+        );
+        Self {
+            target,
+            numerator,
+            denominator,
+        }
+    }
+}
+
+impl<D: DrawTarget> OriginDimensions for Scaled<'_, D> {
+    fn size(&self) -> Size {
+        let size = self.target.bounding_box().size;
+        Size::new(
+            size.width * self.denominator / self.numerator,
+            size.height * self.denominator / self.numerator,
+        )
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for Scaled<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (numerator, denominator) = (self.numerator, self.denominator);
+        // a source pixel is sampled if it is the first one mapped to its scaled position
+        let sampled = |v: i32| (v as u32 * numerator) % denominator < numerator;
+        let scale = |v: i32| (v as u32 * numerator / denominator) as i32;
+        self.target
+            .draw_iter(pixels.into_iter().filter_map(|Pixel(pt, color)| {
+                if sampled(pt.x) && sampled(pt.y) {
+                    Some(Pixel(Point::new(scale(pt.x), scale(pt.y)), color))
+                } else {
+                    None
+                }
+            }))
+    }
+}
+
+// Upscales the pixels drawn on `target` by `x.0 / x.1` horizontally and `y.0 / y.1`
+// vertically, duplicating columns and rows.
+pub(crate) struct Stretched<'t, D> {
+    target: &'t mut D,
+    x: (u32, u32),
+    y: (u32, u32),
+}
+
+impl<'t, D> Stretched<'t, D> {
+    pub(crate) fn new(target: &'t mut D, x: (u32, u32), y: (u32, u32)) -> Self {
+        assert!(
+            x.0 >= x.1 && y.0 >= y.1 && x.1 > 0 && y.1 > 0,
+            "only upscaling is supported"
+        );
+        Self { target, x, y }
+    }
+}
+
+// Range of the stretched coordinates of `v`.
+fn stretch(v: i32, (numerator, denominator): (u32, u32)) -> core::ops::Range<i32> {
+    let v = v as u32;
+    (v * numerator / denominator) as i32..((v + 1) * numerator / denominator) as i32
+}
+
+impl<D: DrawTarget> OriginDimensions for Stretched<'_, D> {
+    fn size(&self) -> Size {
+        let size = self.target.bounding_box().size;
+        Size::new(
+            size.width * self.x.1 / self.x.0,
+            size.height * self.y.1 / self.y.0,
+        )
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for Stretched<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (sx, sy) = (self.x, self.y);
+        self.target
+            .draw_iter(pixels.into_iter().flat_map(|Pixel(pt, color)| {
+                let xs = stretch(pt.x, sx);
+                stretch(pt.y, sy)
+                    .flat_map(move |y| xs.clone().map(move |x| Pixel(Point::new(x, y), color)))
+            }))
+    }
+}
+
+/// Clockwise rotation of `Frame::draw_rotated`.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+// Rotates the pixels of a `size` area drawn on `target` clockwise by `rotation`.
+pub(crate) struct Rotated<'t, D> {
+    target: &'t mut D,
+    rotation: Rotation,
+    size: Size,
+}
+
+impl<'t, D> Rotated<'t, D> {
+    pub(crate) fn new(target: &'t mut D, rotation: Rotation, size: Size) -> Self {
+        Self {
+            target,
+            rotation,
+            size,
+        }
+    }
+}
+
+impl<D: DrawTarget> OriginDimensions for Rotated<'_, D> {
+    fn size(&self) -> Size {
+        let size = self.target.bounding_box().size;
+        match self.rotation {
+            Rotation::Deg0 | Rotation::Deg180 => size,
+            Rotation::Deg90 | Rotation::Deg270 => Size::new(size.height, size.width),
+        }
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for Rotated<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (right, bottom) = (self.size.width as i32 - 1, self.size.height as i32 - 1);
+        let rotate = match self.rotation {
+            Rotation::Deg0 => |pt: Point, _, _| pt,
+            Rotation::Deg90 => |pt: Point, _, bottom| Point::new(bottom - pt.y, pt.x),
+            Rotation::Deg180 => |pt: Point, right, bottom| Point::new(right - pt.x, bottom - pt.y),
+            Rotation::Deg270 => |pt: Point, right, _| Point::new(pt.y, right - pt.x),
+        };
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(pt, color)| Pixel(rotate(pt, right, bottom), color)),
+        )
+    }
+}
+
+// Records the first and last rows and columns of the pixels drawn on `target`.
+pub(crate) struct RowTracker<'t, D> {
+    target: &'t mut D,
+    pub(crate) rows: Option<(i32, i32)>,
+    pub(crate) cols: Option<(i32, i32)>,
+}
+
+impl<'t, D> RowTracker<'t, D> {
+    pub(crate) fn new(target: &'t mut D) -> Self {
+        Self {
+            target,
+            rows: None,
+            cols: None,
+        }
+    }
+}
+
+impl<D: DrawTarget> OriginDimensions for RowTracker<'_, D> {
+    fn size(&self) -> Size {
+        self.target.bounding_box().size
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for RowTracker<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (rows, cols) = (&mut self.rows, &mut self.cols);
+        self.target
+            .draw_iter(pixels.into_iter().inspect(|Pixel(pt, _)| {
+                *rows = match *rows {
+                    Some((first, last)) => Some((first.min(pt.y), last.max(pt.y))),
+                    None => Some((pt.y, pt.y)),
+                };
+                *cols = match *cols {
+                    Some((first, last)) => Some((first.min(pt.x), last.max(pt.x))),
+                    None => Some((pt.x, pt.x)),
+                };
+            }))
+    }
+}
@@ -11,11 +11,12 @@ pub fn take1(input: &[u8]) -> Result<(&[u8], u8), ParseError> {
 
 #[inline]
 pub fn take<const N: usize>(input: &[u8]) -> Result<(&[u8], [u8; N]), ParseError> {
-    if let (Some(value), Some(rest)) = (input.get(0..N), input.get(N..)) {
-        Ok((rest, value.try_into().unwrap()))
-    } else {
-        Err(ParseError::UnexpectedEndOfFile)
-    }
+    let (rest, value) = take_slice(input, N)?;
+    // can't fail as `value` is N bytes long, mapped to an error to stay panic-free
+    let value = value
+        .try_into()
+        .map_err(|_| ParseError::UnexpectedEndOfFile)?;
+    Ok((rest, value))
 }
 
 #[inline]
@@ -61,4 +62,11 @@ mod tests {
         let input = eat_len_prefixed_subblocks(input).unwrap();
         assert_eq!(input, b"\x01\x02\x00");
     }
+
+    #[test]
+    fn test_take() {
+        assert_eq!(take::<2>(b"\x01\x02\x03"), Ok((&b"\x03"[..], [1, 2])));
+        assert_eq!(take::<2>(b"\x01\x02"), Ok((&b""[..], [1, 2])));
+        assert_eq!(take::<2>(b"\x01"), Err(ParseError::UnexpectedEndOfFile));
+    }
 }
@@ -0,0 +1,196 @@
+//! Drawing views of a frame from a decoded tile, for scrolling a viewport across a frame.
+
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::{DrawTarget, OriginDimensions, PixelColor, Point, Size};
+use embedded_graphics::primitives::{PointsIter, Rectangle};
+use embedded_graphics::Pixel;
+
+use crate::{lzw, BlockCursor, ColorTable, DrawError, Frame, LenPrefixRawDataView, ParseError};
+
+/// A frame drawn through a `W` x `H` tile of decoded color indices.
+///
+/// Views inside the decoded tile are drawn by copying the buffered indices. A view outside
+/// of it first decodes a new tile starting at the view, so scrolling by small steps decodes
+/// the frame once per tile instead of once per view. Only the first image block of the
+/// frame is drawn.
+pub struct TiledFrame<'a, C, const W: usize, const H: usize> {
+    frame: Frame<'a, C>,
+    // area of the logical screen held by indices, empty if no tile is decoded
+    tile: Rectangle,
+    color_table: Option<ColorTable<'a>>,
+    indices: [[u8; W]; H],
+}
+
+impl<'a, C, const W: usize, const H: usize> TiledFrame<'a, C, W, H> {
+    /// Wraps `frame`, nothing is decoded until the first view is drawn.
+    pub fn new(frame: Frame<'a, C>) -> Self {
+        Self {
+            frame,
+            tile: Rectangle::zero(),
+            color_table: None,
+            indices: [[0; W]; H],
+        }
+    }
+
+    /// Returns the area of the logical screen held by the decoded tile.
+    pub fn tile(&self) -> Rectangle {
+        self.tile
+    }
+
+    /// Draws the part of the frame inside `view`, in logical screen coordinates.
+    ///
+    /// `ParseError::BufferTooSmall` is returned if the part of the image block inside
+    /// `view` is larger than the tile.
+    pub fn draw_view<D>(
+        &mut self,
+        target: &mut D,
+        view: &Rectangle,
+    ) -> Result<(), DrawError<D::Error>>
+    where
+        C: PixelColor + From<Rgb888>,
+        D: DrawTarget<Color = C>,
+    {
+        let screen = Rectangle::new(Point::zero(), self.frame.size());
+        let view = view
+            .intersection(&screen)
+            .intersection(&self.frame.bounding_box());
+        if view.is_zero_sized() {
+            return Ok(());
+        }
+        if self.tile.intersection(&view) != view {
+            self.decode_tile(&view).map_err(DrawError::Parse)?;
+        }
+
+        let Some(color_table) = self.color_table else {
+            return Err(DrawError::Parse(ParseError::MissingColorTable));
+        };
+        let transparent_color_index = if self.frame.is_transparent {
+            Some(self.frame.transparent_color_index)
+        } else {
+            None
+        };
+        let (tile, indices) = (self.tile, &self.indices);
+        target
+            .draw_iter(view.points().filter_map(|pt| {
+                let offset = pt - tile.top_left;
+                let color_index = indices[offset.y as usize][offset.x as usize];
+                if transparent_color_index == Some(color_index) {
+                    return None;
+                }
+                Some(Pixel(pt, color_table.get(color_index)?.into()))
+            }))
+            .map_err(DrawError::Draw)
+    }
+
+    // Decodes the tile starting at the top left corner of `view`, moved back to fit in
+    // the image block if needed. `view` must be inside the image block.
+    fn decode_tile(&mut self, view: &Rectangle) -> Result<(), ParseError> {
+        if view.size.width as usize > W || view.size.height as usize > H {
+            return Err(ParseError::BufferTooSmall);
+        }
+        let image_block = self.frame.image_block()?;
+        let left = i32::from(image_block.left);
+        let top = i32::from(image_block.top);
+        let right = left + i32::from(image_block.width);
+        let bottom = top + i32::from(image_block.height);
+        let top_left = Point::new(
+            view.top_left.x.min(right - W as i32).max(left),
+            view.top_left.y.min(bottom - H as i32).max(top),
+        );
+        let tile = Rectangle::new(top_left, Size::new(W as u32, H as u32))
+            .intersection(&self.frame.bounding_box());
+        // the tile is only valid once fully decoded
+        self.tile = Rectangle::zero();
+        self.color_table = image_block
+            .local_color_table
+            .or(self.frame.global_color_table);
+
+        // logical screen rows and columns held by the tile
+        let rows = tile.top_left.y as u32..tile.top_left.y as u32 + tile.size.height;
+        let cols = tile.top_left.x as u32..tile.top_left.x as u32 + tile.size.width;
+
+        let raw_image_data = LenPrefixRawDataView::new(image_block.image_data);
+        let mut decoder = lzw::Decoder::new(raw_image_data, image_block.lzw_min_code_size);
+        let mut cursor = BlockCursor::new(&image_block);
+        while let Some(decoded) = decoder.decode_next()? {
+            // codes past the last pixel are ignored
+            for &color_index in decoded.iter().take(cursor.remaining()) {
+                let (x, y) = cursor.advance();
+                if rows.contains(&y) && cols.contains(&x) {
+                    self.indices[(y - rows.start) as usize][(x - cols.start) as usize] =
+                        color_index;
+                }
+            }
+            if cursor.remaining() == 0 || (!image_block.is_interlaced && cursor.y >= rows.end) {
+                // the rest of the image is below the tile
+                self.tile = tile;
+                return Ok(());
+            }
+        }
+        Err(ParseError::UnexpectedEndOfFile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Gif;
+    use embedded_graphics::prelude::ImageDrawable;
+
+    // Records drawn pixels
+    struct Recorder(heapless::Vec<Pixel<Rgb888>, 256>);
+
+    impl OriginDimensions for Recorder {
+        fn size(&self) -> Size {
+            Size::new(160, 80)
+        }
+    }
+
+    impl DrawTarget for Recorder {
+        type Color = Rgb888;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for pixel in pixels {
+                self.0.push(pixel).unwrap();
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_draw_views() {
+        let gif = Gif::<Rgb888>::from_slice(include_bytes!("../assets/Ferris-160x80.gif")).unwrap();
+        for frame in gif.frames().take(3) {
+            let mut tiled = TiledFrame::<_, 32, 24>::new(frame);
+            for x in (0..160).step_by(6) {
+                let view = Rectangle::new(Point::new(x, 30), Size::new(16, 8));
+                let mut expected = Recorder(heapless::Vec::new());
+                frame.draw_sub_image(&mut expected, &view).unwrap();
+                let mut recorder = Recorder(heapless::Vec::new());
+                tiled.draw_view(&mut recorder, &view).unwrap();
+                assert_eq!(recorder.0, expected.0);
+            }
+        }
+
+        let frame = gif.frames().next().unwrap();
+        let mut tiled = TiledFrame::<_, 32, 24>::new(frame);
+        let view = Rectangle::new(Point::new(140, 70), Size::new(16, 8));
+        tiled
+            .draw_view(&mut Recorder(heapless::Vec::new()), &view)
+            .unwrap();
+        // moved back to fit in the image block
+        assert_eq!(
+            tiled.tile(),
+            Rectangle::new(Point::new(128, 56), Size::new(32, 24))
+        );
+        let view = Rectangle::new(Point::new(0, 0), Size::new(40, 8));
+        assert!(matches!(
+            tiled.draw_view(&mut Recorder(heapless::Vec::new()), &view),
+            Err(DrawError::Parse(ParseError::BufferTooSmall))
+        ));
+    }
+}